@@ -1,4 +1,5 @@
 #import bevy_pbr::mesh_view_bindings
+#import bevy_pbr::mesh_bindings
 
 #ifdef CUBEMAP_ARRAY
 @group(1) @binding(0)
@@ -15,7 +16,10 @@ var base_color_sampler: sampler;
 fn fragment(
     #import bevy_pbr::mesh_vertex_output
 ) -> @location(0) vec4<f32> {
-    let fragment_position_view_lh = world_position.xyz * vec3<f32>(1.0, 1.0, -1.0);
+    // direction in the cube's own frame so the skybox can be rotated
+    let model = mat3x3<f32>(mesh.model[0].xyz, mesh.model[1].xyz, mesh.model[2].xyz);
+    let local_direction = transpose(model) * (world_position.xyz - mesh.model[3].xyz);
+    let fragment_position_view_lh = local_direction * vec3<f32>(1.0, 1.0, -1.0);
     return textureSample(
         base_color_texture,
         base_color_sampler,
@@ -0,0 +1,73 @@
+#import bevy_pbr::mesh_view_bindings
+
+struct RingMaterial {
+    color: vec4<f32>,
+    center: vec3<f32>,
+    planet_radius: f32,
+    sun: vec3<f32>,
+    inner_radius: f32,
+    normal: vec3<f32>,
+    outer_radius: f32,
+    shadow: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> material: RingMaterial;
+@group(1) @binding(1)
+var profile_texture: texture_2d<f32>;
+@group(1) @binding(2)
+var profile_sampler: sampler;
+
+// opacity of the rings at a point in their plane, 0 outside of them
+fn ring_opacity(point: vec3<f32>) -> f32 {
+    let radius = length(point - material.center);
+    let u = (radius - material.inner_radius) / (material.outer_radius - material.inner_radius);
+    if (u < 0.0 || u > 1.0) {
+        return 0.0;
+    }
+    // no mipmaps, an explicit level also works outside of uniform control flow
+    return textureSampleLevel(profile_texture, profile_sampler, vec2<f32>(u, 0.5), 0.0).a;
+}
+
+@fragment
+fn fragment(
+    #import bevy_pbr::mesh_vertex_output
+) -> @location(0) vec4<f32> {
+    let position = world_position.xyz;
+    let to_sun = normalize(material.sun - position);
+
+    if (material.shadow > 0.5) {
+        // shell around the planet: darken where the way to the Sun crosses the rings, the night side is dark anyway
+        let up = normalize(position - material.center);
+        let denominator = dot(to_sun, material.normal);
+        if (dot(up, to_sun) <= 0.0 || abs(denominator) < 1e-6) {
+            discard;
+        }
+        let distance = dot(material.center - position, material.normal) / denominator;
+        if (distance <= 0.0) {
+            discard;
+        }
+        let opacity = ring_opacity(position + to_sun * distance);
+        return vec4<f32>(0.0, 0.0, 0.0, opacity * 0.9);
+    }
+
+    let opacity = ring_opacity(position);
+    if (opacity <= 0.0) {
+        discard;
+    }
+    // planet's shadow: does the ray towards the Sun hit the planet?
+    let offset = position - material.center;
+    let b = dot(offset, to_sun);
+    let c = dot(offset, offset) - material.planet_radius * material.planet_radius;
+    var light = 1.0;
+    if (b < 0.0 && b * b - c > 0.0) {
+        light = 0.05;
+    }
+    // the unlit face only shows light scattered through the rings
+    let sun_side = dot(material.sun - material.center, material.normal);
+    let view_side = dot(view.world_position - material.center, material.normal);
+    if (sun_side * view_side < 0.0) {
+        light = light * 0.3 * (1.0 - opacity);
+    }
+    return vec4<f32>(material.color.rgb * light, opacity * material.color.a);
+}
@@ -0,0 +1,15 @@
+#import bevy_pbr::mesh_view_bindings
+
+@fragment
+fn fragment(
+    #import bevy_pbr::mesh_vertex_output
+) -> @location(0) vec4<f32> {
+    // soft disc inside the quad
+    let distance = length(uv - vec2<f32>(0.5, 0.5)) * 2.0;
+    let falloff = clamp(1.0 - distance, 0.0, 1.0);
+#ifdef VERTEX_COLORS
+    return vec4<f32>(color.rgb, color.a * falloff * falloff);
+#else
+    return vec4<f32>(1.0, 1.0, 1.0, falloff * falloff);
+#endif
+}
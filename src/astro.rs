@@ -0,0 +1,58 @@
+use bevy::{prelude::Vec3, math::DVec3};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+/// Obliquity of the ecliptic at J2000 in degrees
+pub const OBLIQUITY: f32 = 23.439_291;
+
+/// Date and time (UTC) the initial state vectors of the bodies refer to
+pub fn sim_epoch() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2022, 11, 25).unwrap().and_hms_opt(0, 0, 0).unwrap()
+}
+
+/// Date and time after `days` of simulation time
+pub fn sim_date(days: f32) -> NaiveDateTime {
+    sim_epoch() + Duration::milliseconds((days as f64 * 86_400_000.0) as i64)
+}
+
+/// Julian date of J2000.0
+pub const J2000: f64 = 2_451_545.0;
+
+pub fn julian_date(date: NaiveDateTime) -> f64 {
+    let j2000 = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap().and_hms_opt(12, 0, 0).unwrap();
+    J2000 + (date - j2000).num_milliseconds() as f64 / 86_400_000.0
+}
+
+/// Julian centuries since J2000.0
+pub fn centuries_since_j2000(date: NaiveDateTime) -> f64 {
+    (julian_date(date) - J2000) / 36_525.0
+}
+
+/// Rotates a vector from the equatorial to the ecliptic frame
+pub fn equatorial_to_ecliptic(v: DVec3) -> DVec3 {
+    let obliquity = (OBLIQUITY as f64).to_radians();
+    DVec3::new(
+        v.x,
+        v.y * obliquity.cos() + v.z * obliquity.sin(),
+        -v.y * obliquity.sin() + v.z * obliquity.cos()
+    )
+}
+
+/// Converts a vector in the TEME frame of SGP4 (true equator, mean equinox of date) to the J2000 ecliptic frame.
+/// Only the general precession in longitude is undone, nutation and the motion of the ecliptic are ignored.
+pub fn teme_to_ecliptic(v: DVec3, date: NaiveDateTime) -> DVec3 {
+    let t = centuries_since_j2000(date);
+    let precession = ((5028.796_195 * t + 1.105_434_8 * t * t) / 3600.0).to_radians();
+    let ecliptic = equatorial_to_ecliptic(v);
+    DVec3::new(
+        ecliptic.x * precession.cos() + ecliptic.y * precession.sin(),
+        -ecliptic.x * precession.sin() + ecliptic.y * precession.cos(),
+        ecliptic.z
+    )
+}
+
+/// Converts equatorial (ICRF) right ascension and declination in degrees to a unit vector
+/// in the ecliptic frame the simulation uses
+pub fn radec_to_ecliptic(ra: f32, dec: f32) -> Vec3 {
+    let (ra, dec) = (ra.to_radians() as f64, dec.to_radians() as f64);
+    equatorial_to_ecliptic(DVec3::new(dec.cos() * ra.cos(), dec.cos() * ra.sin(), dec.sin())).as_vec3()
+}
@@ -0,0 +1,107 @@
+use bevy::prelude::*;
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+
+use crate::{body::Sun, jwst::JWST, targets::FIELD_OF_REGARD, telescope::{resolve_pointing, Telescope}, SimState};
+
+/// Largest roll about the boresight in degrees before the sunshield stops shading the telescope
+pub const ROLL_LIMIT: f32 = 5.0;
+/// Rotation from the axes of the glTF model to the spacecraft frame (x = V2, y = V3, z = V1). The model is y-up
+/// with the telescope above the sunshield and the primary mirror looking along +z, which already matches, a model
+/// authored differently only needs this constant changed.
+const MODEL_TO_SPACECRAFT: Quat = Quat::IDENTITY;
+
+pub struct AttitudePlugin;
+
+impl Plugin for AttitudePlugin {
+
+    fn build(&self, app: &mut App) {
+        app
+        .register_inspectable::<Attitude>()
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(update_attitude.after(resolve_pointing)));
+    }
+
+}
+
+/// Orientation of JWST in the spacecraft frame of the mission: V1 along the boresight, V3 from the sunshield
+/// towards the telescope and V2 completing the right-handed frame. The Sun always lies in the -V3 half, its
+/// angle from V1 is bounded by the field of regard and the roll about V1 by [ROLL_LIMIT].
+#[derive(Component, Inspectable, Default)]
+pub struct Attitude {
+    /// Requested roll about the boresight in degrees
+    pub roll: f32,
+    /// Angle between the boresight and the Sun in degrees
+    #[inspectable(ignore)]
+    pub sun_angle: f32,
+    /// The target is outside the field of regard, the boresight stops at the nearest allowed sun angle
+    #[inspectable(ignore)]
+    pub pitch_violation: bool,
+    /// The requested roll exceeds [ROLL_LIMIT] and is clamped
+    #[inspectable(ignore)]
+    pub roll_violation: bool,
+    #[inspectable(ignore)]
+    pub boresight: Vec3,
+    /// V3 axis in the ecliptic frame
+    #[inspectable(ignore)]
+    pub up: Vec3,
+}
+
+impl Attitude {
+
+    /// Pitch as in the mission's pointing constraints, the sun angle measured from 90°
+    pub fn pitch(&self) -> f32 {
+        self.sun_angle - 90.0
+    }
+
+    /// Rotation from the spacecraft frame (x = V2, y = V3, z = V1) to the ecliptic frame
+    pub fn rotation(&self) -> Quat {
+        Quat::from_mat3(&Mat3::from_cols(self.up.cross(self.boresight), self.up, self.boresight))
+    }
+
+    /// Points the boresight as close to `target` as the constraints allow with the Sun in the direction `to_sun`
+    pub fn point(&mut self, target: Vec3, to_sun: Vec3) {
+        let angle = target.angle_between(to_sun).to_degrees();
+        let allowed = angle.clamp(FIELD_OF_REGARD.0, FIELD_OF_REGARD.1);
+        self.pitch_violation = angle != allowed;
+        self.boresight = if self.pitch_violation {
+            // turn away from the Sun in the plane of the Sun and the target
+            let axis = to_sun.cross(target).try_normalize().unwrap_or_else(|| to_sun.any_orthonormal_vector());
+            Quat::from_axis_angle(axis, allowed.to_radians()) * to_sun
+        } else {
+            target
+        };
+        self.sun_angle = self.boresight.angle_between(to_sun).to_degrees();
+        let roll = self.roll.clamp(-ROLL_LIMIT, ROLL_LIMIT);
+        self.roll_violation = roll != self.roll;
+        let away = -(to_sun - self.boresight * to_sun.dot(self.boresight)).normalize();
+        self.up = Quat::from_axis_angle(self.boresight, roll.to_radians()) * away;
+    }
+
+}
+
+/// Orients JWST towards the target of the telescope, without one the boresight stays perpendicular to the Sun
+/// towards the ecliptic north pole
+pub fn update_attitude(
+    telescope: Res<Telescope>,
+    mut jwst: Query<(&Transform, &mut Attitude, &Children), With<JWST>>,
+    sun: Query<&Transform, (With<Sun>, Without<JWST>)>,
+    mut models: Query<&mut Transform, (With<Handle<Scene>>, Without<JWST>, Without<Sun>)>
+) {
+    let ((transform, mut attitude, children), sun) = match (jwst.get_single_mut(), sun.get_single()) {
+        (Ok(jwst), Ok(sun)) => (jwst, sun),
+        _ => return
+    };
+    let to_sun = match (sun.translation - transform.translation).try_normalize() {
+        Some(to_sun) => to_sun,
+        None => return
+    };
+    let target = telescope.direction
+        .or_else(|| (Vec3::Z - to_sun * to_sun.z).try_normalize())
+        .unwrap_or_else(|| to_sun.any_orthonormal_vector());
+    attitude.point(target, to_sun);
+    let rotation = attitude.rotation() * MODEL_TO_SPACECRAFT;
+    for child in children.iter() {
+        if let Ok(mut model) = models.get_mut(*child) {
+            model.rotation = rotation;
+        }
+    }
+}
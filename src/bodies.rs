@@ -1,18 +1,64 @@
-use bevy::prelude::Vec3;
+use bevy::{prelude::Vec3, math::DQuat};
 
-use crate::body::BodyBundle;
+use crate::{body::BodyBundle, oblateness::Oblateness, rotation::Rotation, rings::RingSystem, radiation::RadiationPressure, drag::{Atmosphere, Drag}, astro::{julian_date, sim_epoch, J2000}, kepler::OrbitalElements, satellites::LocalState};
 
-const AU_TO_UNIT_SCALE: f32 = 10.0;
+pub const AU_TO_UNIT_SCALE: f32 = 10.0;
+pub const KM_TO_UNIT: f32 = AU_TO_UNIT_SCALE / 1.495978707e8;
+/// Converts m/s² to units/day²
+pub const ACCELERATION_TO_UNIT: f32 = 86400.0 * 86400.0 * KM_TO_UNIT / 1000.0;
+/// Speed of light in km/s
+pub const SPEED_OF_LIGHT_KM_S: f64 = 299_792.458;
+/// Speed of light in units/day
+pub const SPEED_OF_LIGHT: f32 = (SPEED_OF_LIGHT_KM_S * 86400.0) as f32 * KM_TO_UNIT;
+/// Mean radius of Earth in km
+pub const EARTH_RADIUS_KM: f64 = 6371.0;
 
 pub struct Body {
     pub model: String,
     pub body: BodyBundle,
     pub radius: f32,
+    /// Physical radius in km
+    pub physical_radius: f32,
+    pub oblateness: Option<Oblateness>,
+    pub rotation: Option<Rotation>,
+    pub rings: Option<RingSystem>,
+    /// Solar radiation pressure model for spacecraft
+    pub radiation: Option<RadiationPressure>,
+    pub atmosphere: Option<Atmosphere>,
+    pub drag: Option<Drag>,
+    /// Catalog number of a satellite, its state comes from the matching element set in the TLE file
+    pub norad_id: Option<u64>,
+    /// Initial state relative to a body spawned before this one, replaces the state of the bundle
+    pub local: Option<LocalState>,
     pub model_scale: f32,
     pub name: String,
     pub unlit: bool
 }
 
+impl Default for Body {
+    
+    fn default() -> Self {
+        Body {
+            model: String::new(),
+            body: BodyBundle::new(0.0, Vec3::ZERO, Vec3::ZERO),
+            radius: 0.0,
+            physical_radius: 0.0,
+            oblateness: None,
+            rotation: None,
+            rings: None,
+            radiation: None,
+            atmosphere: None,
+            drag: None,
+            norad_id: None,
+            local: None,
+            model_scale: 1.0,
+            name: String::new(),
+            unlit: false
+        }
+    }
+    
+}
+
 impl Body {
     
     pub fn earth() -> Self {
@@ -28,9 +74,13 @@ impl Body {
                     -1.552868871220300E-02, 7.906229533085379E-03, 3.064648367334892E-07
                 ) * AU_TO_UNIT_SCALE
             ),
+            physical_radius: EARTH_RADIUS_KM as f32,
+            oblateness: Some(Oblateness::new(1.08263e-3, -2.532e-6, -1.62e-6, 6378.137, 0.0, 90.0)),
+            rotation: Some(Rotation::new(0.0, -0.641, 90.0, -0.557, 190.147, 360.985_623_5)),
+            atmosphere: Some(Atmosphere::new(400.0, 3.7e-12, 58.0, 1000.0, 0.997_270, 0.0, 90.0)),
             model_scale: 0.00001,
             name: "Earth".to_string(),
-            unlit: false
+            ..Default::default()
         }
     }
     
@@ -47,9 +97,13 @@ impl Body {
                     2.906095271828988E-03, 4.545286691593917E-03, -1.944528757086951E-04
                 ) * AU_TO_UNIT_SCALE
             ),
+            physical_radius: 58232.0,
+            oblateness: Some(Oblateness::new(1.6298e-2, 0.0, -9.15e-4, 60268.0, 40.589, 83.537)),
+            rotation: Some(Rotation::new(40.589, -0.036, 83.537, -0.004, 38.90, 810.793_902_4)),
+            rings: Some(RingSystem::saturn()),
             model_scale: 0.00001,
             name: "Saturn".to_string(),
-            unlit: false
+            ..Default::default()
         }
     }
     
@@ -66,9 +120,12 @@ impl Body {
                    -1.243645389952230E-03, 7.811788737744427E-03, -4.555620902846121E-06
                 ) * AU_TO_UNIT_SCALE
             ),
+            physical_radius: 69911.0,
+            oblateness: Some(Oblateness::new(1.4736e-2, 0.0, -5.87e-4, 71492.0, 268.057, 64.495)),
+            rotation: Some(Rotation::new(268.056_595, -0.006_499, 64.495_303, 0.002_413, 284.95, 870.536)),
             model_scale: 0.00001,
             name: "Jupiter".to_string(),
-            unlit: false
+            ..Default::default()
         }
     }
     
@@ -85,9 +142,13 @@ impl Body {
                    -1.252424659948937E-02, 6.220232033014156E-03, 4.378447959849454E-04
                 ) * AU_TO_UNIT_SCALE
             ),
+            physical_radius: 3389.5,
+            oblateness: Some(Oblateness::new(1.96045e-3, 3.15e-5, -1.54e-5, 3396.2, 317.681, 52.887)),
+            rotation: Some(Rotation::new(317.681_43, -0.1061, 52.886_5, -0.0609, 176.630, 350.891_982_26)),
+            atmosphere: Some(Atmosphere::new(0.0, 0.020, 11.1, 250.0, 1.025_957, 317.681, 52.887)),
             model_scale: 0.00001,
             name: "Mars".to_string(),
-            unlit: false
+            ..Default::default()
         }
     }
     
@@ -104,9 +165,13 @@ impl Body {
                    -2.895294668494246E-03, 2.509923332168401E-03, 4.682362547589839E-05
                 ) * AU_TO_UNIT_SCALE
             ),
+            physical_radius: 25362.0,
+            oblateness: Some(Oblateness::new(3.343e-3, 0.0, -2.9e-5, 25559.0, 257.311, -15.175)),
+            rotation: Some(Rotation::new(257.311, 0.0, -15.175, 0.0, 203.81, -501.160_092_8)),
+            rings: Some(RingSystem::uranus()),
             model_scale: 0.00001,
             name: "Uranus".to_string(),
-            unlit: false
+            ..Default::default()
         }
     }
     
@@ -123,9 +188,11 @@ impl Body {
                    2.248220104774973E-02, 2.177869427789603E-03, -1.883369041847100E-03
                 ) * AU_TO_UNIT_SCALE
             ),
+            physical_radius: 2439.7,
+            rotation: Some(Rotation::new(281.0103, -0.0328, 61.4155, -0.0049, 329.5988, 6.138_510_8)),
             model_scale: 0.00001,
             name: "Mercury".to_string(),
-            unlit: false
+            ..Default::default()
         }
     }
     
@@ -142,9 +209,12 @@ impl Body {
                     1.989263354357960E-02, -2.910502841093310E-03, -1.187594747165436E-03
                 ) * AU_TO_UNIT_SCALE
             ),
+            physical_radius: 6051.8,
+            rotation: Some(Rotation::new(272.76, 0.0, 67.16, 0.0, 160.20, -1.481_368_8)),
+            atmosphere: Some(Atmosphere::new(0.0, 65.0, 15.9, 250.0, -243.018_5, 272.76, 67.16)),
             model_scale: 0.00001,
             name: "Venus".to_string(),
-            unlit: false
+            ..Default::default()
         }
     }
     
@@ -161,9 +231,34 @@ impl Body {
                     2.848861795045802E-03, 7.648276574228828E-04, -9.055284692410262E-04
                 ) * AU_TO_UNIT_SCALE
             ),
+            physical_radius: 1188.3,
+            rotation: Some(Rotation::new(132.993, 0.0, -6.163, 0.0, 302.695, 56.362_522_5)),
             model_scale: 0.00001,
             name: "Pluto".to_string(),
-            unlit: false
+            ..Default::default()
+        }
+    }
+    
+    pub fn neptune() -> Self {
+        Body {
+            model: "models/neptune.glb#Scene0".to_string(),
+            radius: 0.005,
+            // from the JPL approximate Keplerian elements instead of Horizons, good to a few arcminutes
+            body: BodyBundle::new(
+                102.409,
+                Vec3::new(
+                    2.974168687467545E+01, -3.058282746162670E+00, -6.224071773984085E-01
+                ) * AU_TO_UNIT_SCALE,
+                Vec3::new(
+                    3.002804845623347E-04, 3.139121331518791E-03, -7.156038698438670E-05
+                ) * AU_TO_UNIT_SCALE
+            ),
+            physical_radius: 24622.0,
+            oblateness: Some(Oblateness::new(3.4083e-3, 0.0, -3.33e-5, 25225.0, 299.36, 43.46)),
+            rotation: Some(Rotation::new(299.36, 0.0, 43.46, 0.0, 249.978, 541.139_775_7)),
+            model_scale: 0.00001,
+            name: "Neptune".to_string(),
+            ..Default::default()
         }
     }
     
@@ -180,9 +275,12 @@ impl Body {
                 -1.491883668334010E-02, 7.773993419863166E-03, -4.679176055656679E-05
                 ) * AU_TO_UNIT_SCALE
             ),
+            physical_radius: 1737.4,
+            oblateness: Some(Oblateness::new(2.0323e-4, 0.0, 0.0, 1738.0, 269.995, 66.539)),
+            rotation: Some(Rotation::new(269.9949, 0.0031, 66.5392, 0.0130, 38.3213, 13.176_358_15)),
             model_scale: 0.000003,
             name: "Moon".to_string(),
-            unlit: false
+            ..Default::default()
         }
     }
     
@@ -199,48 +297,106 @@ impl Body {
                 -1.564139006806661E-02, 7.940335006606503E-03, -9.026475694712961E-05
                 ) * AU_TO_UNIT_SCALE
             ),
+            physical_radius: 0.011,
+            radiation: Some(RadiationPressure { area: 300.0, reflectivity: 1.5 }),
             model_scale: 0.0003,
             name: "JWST".to_string(),
-            unlit: true
+            unlit: true,
+            ..Default::default()
         }
     }
     
     pub fn iss() -> Self {
         Body {
             model: "models/iss.glb#Scene0".to_string(),
-            radius: 0.002,
-            body: BodyBundle::new(
-                0.000000000000000000000444615,
-                Vec3::new(
-                4.488043238527515E-01, 8.751376110417752E-01, 1.941969204321329E-04
-                ) * AU_TO_UNIT_SCALE,
-                Vec3::new(
-                -1.504701637582184E-02, 1.177161600016825E-02, -2.108766983760775E-03
-                ) * AU_TO_UNIT_SCALE
-            ),
+            radius: 0.0005,
+            body: BodyBundle::new(0.000000000000000000000444615, Vec3::ZERO, Vec3::ZERO),
+            physical_radius: 0.055,
+            radiation: Some(RadiationPressure { area: 2500.0, reflectivity: 1.3 }),
+            drag: Some(Drag { ballistic_coefficient: 140.0 }),
+            norad_id: Some(25544),
             model_scale: 0.000003,
             name: "ISS".to_string(),
-            unlit: false
+            ..Default::default()
         }
     }
     
     pub fn hubble() -> Self {
         Body {
             model: "models/hubble.glb#Scene0".to_string(),
-            radius: 0.002,
-            body: BodyBundle::new(
-                11600.0 / f32::powf(10.0, 24.0),
-                Vec3::new(
-                4.487378270154649E-01, 8.751063507720495E-01, 1.817069672476064E-04
-                ) * AU_TO_UNIT_SCALE,
-                Vec3::new(
-                -1.303624904379408E-02, 5.738929759261992E-03, 2.893730048309336E-03
-                ) * AU_TO_UNIT_SCALE
-            ),
+            radius: 0.0005,
+            body: BodyBundle::new(11600.0 / f32::powf(10.0, 24.0), Vec3::ZERO, Vec3::ZERO),
+            physical_radius: 0.0066,
+            radiation: Some(RadiationPressure { area: 70.0, reflectivity: 1.3 }),
+            drag: Some(Drag { ballistic_coefficient: 130.0 }),
+            norad_id: Some(20580),
             model_scale: 0.000003,
             name: "Hubble".to_string(),
-            unlit: false
+            ..Default::default()
         }
     }
     
-}
\ No newline at end of file
+    pub fn io() -> Self {
+        Self::moon_of(&Body::jupiter(), "Io", 0.089_319, 1821.6, [421_800.0, 0.004, 0.0, 0.0, 49.1, 330.9], 1.769_138)
+    }
+    
+    pub fn europa() -> Self {
+        Self::moon_of(&Body::jupiter(), "Europa", 0.047_998, 1560.8, [671_100.0, 0.009, 0.5, 184.0, 45.0, 345.4], 3.551_181)
+    }
+    
+    pub fn ganymede() -> Self {
+        Self::moon_of(&Body::jupiter(), "Ganymede", 0.148_19, 2634.1, [1_070_400.0, 0.001, 0.2, 58.5, 198.3, 324.8], 7.154_553)
+    }
+    
+    pub fn callisto() -> Self {
+        Self::moon_of(&Body::jupiter(), "Callisto", 0.107_59, 2410.3, [1_882_700.0, 0.007, 0.3, 309.1, 43.8, 87.4], 16.689_018)
+    }
+    
+    pub fn titan() -> Self {
+        Self::moon_of(&Body::saturn(), "Titan", 0.134_55, 2574.7, [1_221_900.0, 0.029, 0.3, 28.1, 180.5, 163.3], 15.945_448)
+    }
+    
+    pub fn triton() -> Self {
+        Self::moon_of(&Body::neptune(), "Triton", 0.021_39, 1353.4, [354_800.0, 0.0, 156.8, 177.6, 0.0, 264.8], 5.876_854)
+    }
+    
+    pub fn phobos() -> Self {
+        Self::moon_of(&Body::mars(), "Phobos", 1.0659e-8, 11.08, [9_380.0, 0.015, 1.1, 164.9, 150.1, 92.5], 0.318_910)
+    }
+    
+    pub fn deimos() -> Self {
+        Self::moon_of(&Body::mars(), "Deimos", 1.4762e-9, 6.2, [23_460.0, 0.000, 1.8, 339.6, 290.5, 296.2], 1.262_441)
+    }
+    
+    /// Moon of `parent` given by the mean elements at J2000 [semi-major axis (km), eccentricity, inclination, node,
+    /// argument of periapsis, mean anomaly] in degrees relative to the parent's equator and the sidereal period in days.
+    /// The mean anomaly is advanced to the simulation epoch with the period, the precession of the node and
+    /// periapsis is ignored.
+    fn moon_of(parent: &Body, name: &str, mass: f32, physical_radius: f32, elements: [f64; 6], period: f64) -> Self {
+        let [semi_major_axis, eccentricity, inclination, node, periapsis, mean_anomaly] = elements;
+        let epoch = julian_date(sim_epoch());
+        // G in km³/(s² 10^24 kg), the state is integrated in km/day
+        let mu = 66_743.0 * (parent.body.mass() + mass) as f64 * 86400.0 * 86400.0;
+        let mean_anomaly = mean_anomaly + 360.0 * (epoch - J2000) / period;
+        let (position, velocity) = OrbitalElements::around(mu, semi_major_axis, eccentricity, inclination, node, periapsis, mean_anomaly, epoch)
+            .state_around(mu, epoch);
+        let equator = parent.rotation.map_or(DQuat::IDENTITY, |rotation| rotation.equator(J2000));
+        let radius = if physical_radius > 1000.0 { 0.001 } else { 0.0005 };
+        Body {
+            model: "models/moon.glb#Scene0".to_string(),
+            radius,
+            body: BodyBundle::new(mass, Vec3::ZERO, Vec3::ZERO),
+            physical_radius,
+            local: Some(LocalState {
+                parent: parent.name.clone(),
+                position: equator * position,
+                velocity: equator * velocity / 86400.0
+            }),
+            // the Moon's model at the Moon's mesh radius
+            model_scale: 0.000003 * radius / 0.002,
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+    
+}
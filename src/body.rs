@@ -1,7 +1,7 @@
-use crate::{camera::PanOrbitCamera, lagrange::calculate_lagrange_points, speed::Speed, jwst::{JWST, orbit_around_l2}, SimState};
+use crate::{camera::PanOrbitCamera, lagrange::calculate_lagrange_points, speed::Speed, jwst::orbit_around_l2, SimState, bodies::SPEED_OF_LIGHT, relativity::Relativity, oblateness::{Oblateness, ZonalHarmonics}, radiation::{RadiationPressure, SolarRadiation}, eclipse::illumination, drag::{Atmosphere, AtmosphericDrag, Drag}};
 use bevy::{
     prelude::{
-        App, Bundle, Component, IntoSystemDescriptor, Mut, Name, Plugin, Query,
+        App, Bundle, Component, IntoSystemDescriptor, Name, Plugin, Query,
         Res, Resource, SystemLabel, SystemSet, Transform, Vec3, Deref, Without
     },
     time::Time,
@@ -27,6 +27,13 @@ impl Default for Gravity {
 #[derive(Component, Inspectable)]
 pub struct Sun;
 
+#[derive(Component, Inspectable)]
+pub struct Spacecraft;
+
+/// Moved by its own system instead of the N-body integrator
+#[derive(Component, Inspectable)]
+pub struct Kinematic;
+
 #[derive(Default, Component, Inspectable)]
 pub struct Velocity(pub Vec3);
 
@@ -36,6 +43,10 @@ pub struct Acceleration(pub Vec3);
 #[derive(Component, Inspectable)]
 pub struct Mass(pub f32);
 
+/// Physical radius in units
+#[derive(Component, Inspectable, Default)]
+pub struct Radius(pub f32);
+
 #[derive(Component, Inspectable)]
 pub struct EnableLines(pub bool);
 
@@ -80,6 +91,24 @@ impl BodyBundle {
             enable_lines: EnableLines::default()
         }
     }
+
+    pub fn mass(&self) -> f32 {
+        self.mass.0
+    }
+
+    pub fn position(&self) -> Vec3 {
+        self.transform.translation
+    }
+
+    pub fn velocity(&self) -> Vec3 {
+        self.vel.0
+    }
+
+    /// Moves the initial state from a frame moving with `pos` and `vel` into the global frame
+    pub fn offset(&mut self, pos: Vec3, vel: Vec3) {
+        self.transform.translation += pos;
+        self.vel.0 += vel;
+    }
 }
 
 pub struct BodyPlugin;
@@ -91,41 +120,290 @@ impl Plugin for BodyPlugin {
             .register_inspectable::<Mass>()
             .register_inspectable::<Velocity>()
             .register_inspectable::<Sun>()
+            .register_inspectable::<Spacecraft>()
+            .register_inspectable::<Kinematic>()
             .register_inspectable::<Acceleration>()
             .register_inspectable::<BodyBundle>()
             .register_inspectable::<Lines>()
+            .register_inspectable::<Radius>()
             .init_resource::<Pause>()
+            .init_resource::<CloseEncounters>()
+            .register_inspectable::<CloseEncounters>()
             .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(body_focus.after(orbit_around_l2)))
             .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(update_bodies));
     }
 }
 
+/// Optional measures against close encounters blowing up the simulation
+#[derive(Resource, Inspectable)]
+pub struct CloseEncounters {
+    /// Plummer softening: the force between two bodies uses (d² + ε²) instead of d²
+    pub softening: bool,
+    /// Softening length ε in units
+    pub softening_length: f32,
+    /// Split the frame's step into substeps when bodies come close to each other
+    pub refine_steps: bool,
+    /// Fraction of the shortest encounter timescale a single substep may cover
+    pub accuracy: f32,
+    pub max_substeps: u32,
+}
+
+impl Default for CloseEncounters {
+    
+    fn default() -> Self {
+        Self {
+            softening: false,
+            softening_length: 0.001,
+            refine_steps: false,
+            accuracy: 0.05,
+            max_substeps: 1000
+        }
+    }
+    
+}
+
+/// State of a body while integrating
+#[derive(Clone, Copy)]
+pub struct BodyState {
+    pub pos: Vec3,
+    pub vel: Vec3,
+    pub mass: f32,
+    /// Physical radius, used for shadows
+    pub radius: f32,
+    pub oblateness: Option<Oblateness>,
+    pub radiation: Option<RadiationPressure>,
+    pub atmosphere: Option<Atmosphere>,
+    pub drag: Option<Drag>,
+}
+
+/// Indices of the bodies with mass and of the massless test particles
+fn split_test_particles(bodies: &[BodyState]) -> (Vec<usize>, Vec<usize>) {
+    (0..bodies.len()).partition(|&i| bodies[i].mass != 0.0)
+}
+
+/// Forces acting between the bodies, independent of the ECS so it can also be used to look ahead in time
+pub struct ForceModel {
+    pub g: f32,
+    /// Plummer softening length, 0 for plain newtonian gravity
+    pub softening: f32,
+    /// Index of the Sun in the integrated bodies
+    pub sun: Option<usize>,
+    /// Scale of the post-newtonian correction towards the Sun, 0 disables it
+    pub relativity: f32,
+    /// Include the zonal harmonics of flattened bodies
+    pub zonal_harmonics: bool,
+    /// Include solar radiation pressure, needs `sun`
+    pub radiation_pressure: bool,
+    /// Include the drag of atmospheres
+    pub drag: bool,
+}
+
+impl ForceModel {
+    
+    pub fn accelerations(&self, bodies: &[BodyState]) -> Vec<Vec3> {
+        let mut acc = vec![Vec3::ZERO; bodies.len()];
+        let softening_squared = self.softening * self.softening;
+        let (massive, particles) = split_test_particles(bodies);
+        for (n, &i) in massive.iter().enumerate() {
+            for &j in massive[(n + 1)..].iter() {
+                let diff = bodies[j].pos - bodies[i].pos;
+                let distance_squared = diff.length_squared() + softening_squared;
+                if distance_squared > 0.0 {
+                    let factor = self.g / (distance_squared * distance_squared.sqrt());
+                    acc[i] += diff * factor * bodies[j].mass;
+                    acc[j] -= diff * factor * bodies[i].mass;
+                }
+            }
+        }
+        // test particles only feel the massive bodies and don't pull back
+        for &i in particles.iter() {
+            for &j in massive.iter() {
+                let diff = bodies[j].pos - bodies[i].pos;
+                let distance_squared = diff.length_squared() + softening_squared;
+                if distance_squared > 0.0 {
+                    acc[i] += diff * (self.g * bodies[j].mass / (distance_squared * distance_squared.sqrt()));
+                }
+            }
+        }
+        if self.zonal_harmonics {
+            self.add_zonal_harmonics(bodies, &mut acc);
+        }
+        if self.drag {
+            self.add_drag(bodies, &mut acc);
+        }
+        if let Some(sun) = self.sun {
+            if self.relativity > 0.0 {
+                self.add_relativistic_correction(bodies, sun, &mut acc);
+            }
+            if self.radiation_pressure {
+                self.add_radiation_pressure(bodies, sun, &mut acc);
+            }
+        }
+        acc
+    }
+    
+    /// Pull of the equatorial bulges of flattened bodies on all other bodies, the flattened body takes the reaction
+    fn add_zonal_harmonics(&self, bodies: &[BodyState], acc: &mut [Vec3]) {
+        for (i, body) in bodies.iter().enumerate() {
+            let oblateness = match body.oblateness {
+                Some(oblateness) => oblateness,
+                None => continue
+            };
+            let gm = self.g * body.mass;
+            for (j, other) in bodies.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                let a = oblateness.acceleration(gm, other.pos - body.pos);
+                acc[j] += a;
+                if body.mass > 0.0 {
+                    acc[i] -= a * (other.mass / body.mass);
+                }
+            }
+        }
+    }
+    
+    /// Drag of the atmospheres on bodies passing through them
+    fn add_drag(&self, bodies: &[BodyState], acc: &mut [Vec3]) {
+        for (i, body) in bodies.iter().enumerate() {
+            let drag = match body.drag {
+                Some(drag) => drag,
+                None => continue
+            };
+            for (j, planet) in bodies.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                if let Some(atmosphere) = planet.atmosphere {
+                    acc[i] += atmosphere.acceleration(&drag, planet.radius, body.pos - planet.pos, body.vel - planet.vel);
+                }
+            }
+        }
+    }
+    
+    /// Sunlight pushing bodies with a radiation pressure model away from the Sun, reduced by the shadows of the other bodies
+    fn add_radiation_pressure(&self, bodies: &[BodyState], sun: usize, acc: &mut [Vec3]) {
+        let sun_state = bodies[sun];
+        for (i, body) in bodies.iter().enumerate() {
+            let radiation = match body.radiation {
+                Some(radiation) => radiation,
+                None => continue
+            };
+            if i == sun {
+                continue;
+            }
+            let mut light = 1.0;
+            for (j, occulter) in bodies.iter().enumerate() {
+                if j != i && j != sun && occulter.radius > 0.0 {
+                    light *= illumination(body.pos, (sun_state.pos, sun_state.radius), (occulter.pos, occulter.radius));
+                }
+            }
+            if light > 0.0 {
+                acc[i] += radiation.acceleration(body.mass, body.pos - sun_state.pos) * light;
+            }
+        }
+    }
+    
+    /// First post-newtonian (Schwarzschild) correction for bodies orbiting the Sun:
+    /// a = GM / (c² r³) * ((4GM / r - v²) r + 4 (r·v) v)
+    fn add_relativistic_correction(&self, bodies: &[BodyState], sun: usize, acc: &mut [Vec3]) {
+        let gm = self.g * bodies[sun].mass;
+        let c_squared = SPEED_OF_LIGHT * SPEED_OF_LIGHT;
+        for (i, body) in bodies.iter().enumerate() {
+            if i == sun {
+                continue;
+            }
+            let r = body.pos - bodies[sun].pos;
+            let v = body.vel - bodies[sun].vel;
+            let distance = r.length();
+            if distance > 0.0 {
+                let correction = r * (4.0 * gm / distance - v.length_squared()) + v * (4.0 * r.dot(v));
+                acc[i] += correction * (gm / (c_squared * distance.powi(3)) * self.relativity);
+            }
+        }
+    }
+    
+    /// Number of substeps needed so that no step covers more than `accuracy` of the shortest
+    /// encounter timescale (free-fall time or crossing time) between any two massive bodies,
+    /// test particles don't set the step
+    pub fn substeps(&self, bodies: &[BodyState], dt: f32, accuracy: f32, max_substeps: u32) -> u32 {
+        let mut timescale = f32::INFINITY;
+        let (massive, _) = split_test_particles(bodies);
+        for (n, &i) in massive.iter().enumerate() {
+            for &j in massive[(n + 1)..].iter() {
+                let distance = ((bodies[j].pos - bodies[i].pos).length_squared() + self.softening * self.softening).sqrt();
+                let gm = self.g * (bodies[i].mass + bodies[j].mass);
+                if gm > 0.0 {
+                    timescale = timescale.min((distance.powi(3) / gm).sqrt());
+                }
+                let speed = (bodies[j].vel - bodies[i].vel).length();
+                if speed > 0.0 {
+                    timescale = timescale.min(distance / speed);
+                }
+            }
+        }
+        ((dt.abs() / (accuracy * timescale)).ceil() as u32).clamp(1, max_substeps.max(1))
+    }
+    
+    /// Advances all bodies with semi-implicit euler steps, returns the last accelerations
+    pub fn step(&self, bodies: &mut [BodyState], dt: f32, substeps: u32) -> Vec<Vec3> {
+        let h = dt / substeps as f32;
+        let mut acc = Vec::new();
+        for _ in 0..substeps {
+            acc = self.accelerations(bodies);
+            for (body, a) in bodies.iter_mut().zip(acc.iter()) {
+                body.vel += *a * h;
+                body.pos += body.vel * h;
+            }
+        }
+        acc
+    }
+    
+}
+
 pub fn update_bodies(
     g: Res<Gravity>,
-    mut query: Query<(&Name, &Mass, &mut Transform, &mut Acceleration, &mut Velocity, Without<JWST>)>,
+    encounters: Res<CloseEncounters>,
+    relativity: Res<Relativity>,
+    harmonics: Res<ZonalHarmonics>,
+    radiation: Res<SolarRadiation>,
+    drag: Res<AtmosphericDrag>,
+    mut query: Query<(&Mass, &mut Transform, &mut Acceleration, &mut Velocity, Option<&Sun>, (Option<&Radius>, Option<&Oblateness>, Option<&RadiationPressure>, Option<&Atmosphere>, Option<&Drag>)), Without<Kinematic>>,
     time: Res<Time>,
     speed: Res<Speed>,
     paused: Res<Pause>
 ) {
     if !paused.0 {
-        let mut bodies: Vec<(&Mass, Mut<Transform>, Mut<Acceleration>)> = Vec::new();
-        for (_, mass, transform, mut acc, _, _) in query.iter_mut() {
-            acc.0 = Vec3::ZERO;
-            for (other_mass, other_pos, other_acc) in bodies.iter_mut() {
-                let diff = other_pos.translation - transform.translation;
-                if let Some(mut force) = diff.try_normalize() {
-                    let magnitude = g.0 * mass.0 * other_mass.0 / diff.length_squared();
-                    force *= magnitude;
-                    acc.0 += force;
-                    other_acc.0 -= force;
-                }
-            }
-            bodies.push((mass, transform, acc));
-        }
-        for (_m, mass, mut transform, mut acc, mut vel, _) in query.iter_mut() {
-            acc.0 /= mass.0;
-            vel.0 += acc.0 * time.delta_seconds() * speed.0;
-            transform.translation += vel.0 * time.delta_seconds() * speed.0;
+        let mut bodies: Vec<BodyState> = query.iter().map(|(mass, transform, _, velocity, _, (radius, oblateness, radiation, atmosphere, drag))| BodyState {
+            pos: transform.translation,
+            vel: velocity.0,
+            mass: mass.0,
+            radius: radius.map_or(0.0, |radius| radius.0),
+            oblateness: oblateness.copied(),
+            radiation: radiation.copied(),
+            atmosphere: atmosphere.copied(),
+            drag: drag.copied()
+        }).collect();
+        let model = ForceModel {
+            g: g.0,
+            softening: if encounters.softening { encounters.softening_length } else { 0.0 },
+            sun: query.iter().position(|(_, _, _, _, sun, _)| sun.is_some()),
+            relativity: if relativity.enabled { relativity.factor } else { 0.0 },
+            zonal_harmonics: harmonics.enabled,
+            radiation_pressure: radiation.enabled,
+            drag: drag.enabled
+        };
+        let dt = time.delta_seconds() * speed.0;
+        let substeps = if encounters.refine_steps {
+            model.substeps(&bodies, dt, encounters.accuracy, encounters.max_substeps)
+        } else {
+            1
+        };
+        let acc = model.step(&mut bodies, dt, substeps);
+        for ((_, mut transform, mut acceleration, mut velocity, _, _), (body, a)) in query.iter_mut().zip(bodies.iter().zip(acc.iter())) {
+            acceleration.0 = *a;
+            velocity.0 = body.vel;
+            transform.translation = body.pos;
         }
     }
 }
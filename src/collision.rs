@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+
+use crate::{bodies::KM_TO_UNIT, body::{update_bodies, Kinematic, Mass, Pause, Radius, Velocity}, minor_bodies::MinorBody, speed::Speed, ui::SimTime, SimState};
+
+pub struct CollisionPlugin;
+
+impl Plugin for CollisionPlugin {
+
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<Collisions>()
+        .register_inspectable::<Collisions>()
+        .add_event::<CollisionEvent>()
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(detect_collisions.after(update_bodies)));
+    }
+
+}
+
+#[derive(Inspectable, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CollisionOutcome {
+    /// Inelastic merge into the heavier body, conserving mass and momentum
+    Merge,
+    Bounce,
+    /// Removes the lighter body
+    Despawn,
+}
+
+#[derive(Resource, Inspectable)]
+pub struct Collisions {
+    pub enabled: bool,
+    pub outcome: CollisionOutcome,
+    /// Coefficient of restitution used for bounces (1 = elastic)
+    pub restitution: f32,
+}
+
+impl Default for Collisions {
+
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            outcome: CollisionOutcome::Merge,
+            restitution: 0.8
+        }
+    }
+
+}
+
+/// Sent when two bodies touch
+pub struct CollisionEvent {
+    pub first: String,
+    pub second: String,
+    pub outcome: CollisionOutcome,
+    /// Relative speed at impact in km/s
+    pub speed: f32,
+    /// Simulation time in days
+    pub time: f32,
+}
+
+/// Checks all pairs of integrated bodies. Satellites on a local orbit (kinematic, drawn at an exaggerated distance)
+/// handle their impacts themselves and minor bodies are left out to keep the pair count down.
+fn detect_collisions(
+    mut commands: Commands,
+    mut bodies: Query<(Entity, &Name, &mut Radius, &mut Mass, &mut Transform, &mut Velocity), (Without<Kinematic>, Without<MinorBody>)>,
+    mut events: EventWriter<CollisionEvent>,
+    settings: Res<Collisions>,
+    time: Res<Time>,
+    speed: Res<Speed>,
+    pause: Res<Pause>,
+    sim_time: Res<SimTime>
+) {
+    if !settings.enabled || pause.0 {
+        return;
+    }
+    let dt = time.delta_seconds() * speed.0;
+    let mut removed: Vec<Entity> = Vec::new();
+    let mut combinations = bodies.iter_combinations_mut();
+    while let Some([first, second]) = combinations.fetch_next() {
+        let (entity1, name1, mut radius1, mut mass1, mut transform1, mut velocity1) = first;
+        let (entity2, name2, mut radius2, mut mass2, mut transform2, mut velocity2) = second;
+        if removed.contains(&entity1) || removed.contains(&entity2) {
+            continue;
+        }
+        // check the whole path covered during the last step, fast bodies could tunnel through otherwise
+        let relative = transform1.translation - transform2.translation;
+        let relative_velocity = velocity1.0 - velocity2.0;
+        let previous = relative - relative_velocity * dt;
+        let path = relative - previous;
+        let t = if path.length_squared() > 0.0 {
+            (-previous.dot(path) / path.length_squared()).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        let closest = previous + path * t;
+        if closest.length() >= radius1.0 + radius2.0 {
+            continue;
+        }
+
+        let total_mass = mass1.0 + mass2.0;
+        // share of the reaction each body takes, test particles without mass take all of it
+        let (share1, share2) = if total_mass > 0.0 {
+            (mass2.0 / total_mass, mass1.0 / total_mass)
+        } else {
+            (0.5, 0.5)
+        };
+        match settings.outcome {
+            CollisionOutcome::Merge => {
+                let position = if total_mass > 0.0 {
+                    (transform1.translation * mass1.0 + transform2.translation * mass2.0) / total_mass
+                } else {
+                    transform1.translation
+                };
+                let velocity = if total_mass > 0.0 {
+                    (velocity1.0 * mass1.0 + velocity2.0 * mass2.0) / total_mass
+                } else {
+                    velocity1.0
+                };
+                // keep the volume of both bodies
+                let merged_radius = (radius1.0.powi(3) + radius2.0.powi(3)).cbrt();
+                let (survivor, other) = if mass1.0 >= mass2.0 {
+                    (entity1, entity2)
+                } else {
+                    (entity2, entity1)
+                };
+                if survivor == entity1 {
+                    mass1.0 = total_mass;
+                    radius1.0 = merged_radius;
+                    transform1.translation = position;
+                    velocity1.0 = velocity;
+                } else {
+                    mass2.0 = total_mass;
+                    radius2.0 = merged_radius;
+                    transform2.translation = position;
+                    velocity2.0 = velocity;
+                }
+                commands.entity(other).despawn_recursive();
+                removed.push(other);
+            },
+            CollisionOutcome::Bounce => {
+                let normal = match relative.try_normalize() {
+                    Some(normal) => normal,
+                    None => continue
+                };
+                let approach = relative_velocity.dot(normal);
+                if approach >= 0.0 {
+                    // already moving apart after an earlier bounce
+                    continue;
+                }
+                let impulse = -(1.0 + settings.restitution) * approach;
+                velocity1.0 += normal * impulse * share1;
+                velocity2.0 -= normal * impulse * share2;
+                // separate the bodies so they don't stick together
+                let overlap = radius1.0 + radius2.0 - relative.length();
+                if overlap > 0.0 {
+                    transform1.translation += normal * overlap * share1;
+                    transform2.translation -= normal * overlap * share2;
+                }
+            },
+            CollisionOutcome::Despawn => {
+                let lighter = if mass1.0 >= mass2.0 { entity2 } else { entity1 };
+                commands.entity(lighter).despawn_recursive();
+                removed.push(lighter);
+            }
+        }
+        events.send(CollisionEvent {
+            first: name1.to_string(),
+            second: name2.to_string(),
+            outcome: settings.outcome,
+            speed: relative_velocity.length() / KM_TO_UNIT / (3600.0 * 24.0),
+            time: sim_time.0
+        });
+    }
+}
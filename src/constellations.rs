@@ -0,0 +1,170 @@
+use bevy::{prelude::*, render::{mesh::PrimitiveTopology, view::NoFrustumCulling}, pbr::NotShadowCaster};
+use bevy_egui::{egui, EguiContext};
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+
+use crate::{astro::radec_to_ecliptic, camera::PanOrbitCamera, lines::line_mesh};
+
+const CONSTELLATION_FILE: &str = "assets/stars/constellations.txt";
+/// Radius of the sphere the figures are drawn on, just inside the stars
+const SPHERE_RADIUS: f32 = 850.0;
+
+pub struct ConstellationPlugin;
+
+impl Plugin for ConstellationPlugin {
+
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<Constellations>()
+        .register_inspectable::<Constellations>()
+        .add_startup_system(spawn_constellations)
+        .add_system(update_constellations)
+        .add_system(constellation_labels);
+    }
+
+}
+
+#[derive(Resource, Inspectable)]
+pub struct Constellations {
+    pub visible: bool,
+    pub labels: bool,
+    /// Number of figures in the file, which has fewer than the 88 IAU constellations
+    #[inspectable(ignore)]
+    pub count: usize,
+}
+
+impl Default for Constellations {
+
+    fn default() -> Self {
+        Self {
+            visible: false,
+            labels: true,
+            count: 0
+        }
+    }
+
+}
+
+/// Stick figure of a constellation as polylines of directions in the ecliptic frame
+pub struct Constellation {
+    pub name: String,
+    pub lines: Vec<Vec<Vec3>>,
+}
+
+impl Constellation {
+
+    /// Direction of the label, the mean of all points of the figure
+    pub fn center(&self) -> Vec3 {
+        self.lines.iter().flatten().fold(Vec3::ZERO, |sum, point| sum + *point).normalize_or_zero()
+    }
+
+}
+
+/// Line mesh of all figures, centered on the camera like the stars
+#[derive(Component)]
+pub struct ConstellationLines(Vec<(String, Vec3)>);
+
+/// Reads lines of the form `Name; ra dec, ra dec; ra dec, ...` with the right ascension in hours and the declination
+/// in degrees, lines starting with # are comments
+pub fn parse_constellations(text: &str) -> Vec<Constellation> {
+    text.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut parts = line.split(';');
+            let name = parts.next()?.trim().to_string();
+            let lines = parts.map(|polyline| {
+                polyline.split(',').filter_map(|point| {
+                    let mut coordinates = point.split_whitespace().map(|value| value.parse::<f32>());
+                    match (coordinates.next(), coordinates.next()) {
+                        (Some(Ok(ra)), Some(Ok(dec))) => Some(radec_to_ecliptic(ra * 15.0, dec)),
+                        _ => {
+                            warn!("Invalid point '{}' in constellation {}", point.trim(), name);
+                            None
+                        }
+                    }
+                }).collect::<Vec<Vec3>>()
+            }).filter(|polyline| polyline.len() > 1).collect();
+            Some(Constellation { name, lines })
+        })
+        .collect()
+}
+
+fn spawn_constellations(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut settings: ResMut<Constellations>
+) {
+    let constellations = match std::fs::read_to_string(CONSTELLATION_FILE) {
+        Ok(text) => parse_constellations(&text),
+        Err(error) => {
+            warn!("Could not read {}: {}", CONSTELLATION_FILE, error);
+            return;
+        }
+    };
+    settings.count = constellations.len();
+    let segments = constellations.iter()
+        .flat_map(|constellation| constellation.lines.iter())
+        .flat_map(|polyline| polyline.windows(2).flat_map(|pair| [pair[0] * SPHERE_RADIUS, pair[1] * SPHERE_RADIUS]))
+        .collect();
+    commands.spawn(MaterialMeshBundle {
+        mesh: meshes.add(line_mesh(PrimitiveTopology::LineList, segments)),
+        material: materials.add(StandardMaterial {
+            base_color: Color::rgb(0.25, 0.4, 0.7),
+            unlit: true,
+            ..default()
+        }),
+        visibility: Visibility::INVISIBLE,
+        ..default()
+    })
+    .insert(NotShadowCaster)
+    .insert(NoFrustumCulling)
+    .insert(ConstellationLines(constellations.iter().map(|constellation| (constellation.name.clone(), constellation.center())).collect()));
+}
+
+fn update_constellations(
+    settings: Res<Constellations>,
+    camera: Query<&Transform, With<PanOrbitCamera>>,
+    mut lines: Query<(&mut Transform, &mut Visibility), (With<ConstellationLines>, Without<PanOrbitCamera>)>
+) {
+    if let (Ok(camera), Ok((mut transform, mut visibility))) = (camera.get_single(), lines.get_single_mut()) {
+        transform.translation = camera.translation;
+        visibility.is_visible = settings.visible;
+    }
+}
+
+/// Writes the names at the projected centers of the figures
+fn constellation_labels(
+    mut egui_context: ResMut<EguiContext>,
+    settings: Res<Constellations>,
+    camera: Query<(&Camera, &GlobalTransform), With<PanOrbitCamera>>,
+    lines: Query<&ConstellationLines>
+) {
+    if !settings.visible || !settings.labels {
+        return;
+    }
+    let ((camera, camera_transform), labels) = match (camera.get_single(), lines.get_single()) {
+        (Ok(camera), Ok(lines)) => (camera, &lines.0),
+        _ => return
+    };
+    let painter = egui_context.ctx_mut().layer_painter(egui::LayerId::background());
+    for (name, direction) in labels.iter() {
+        let position = camera_transform.translation() + *direction * SPHERE_RADIUS;
+        if let Some(point) = project(camera, camera_transform, position) {
+            painter.text(
+                point,
+                egui::Align2::CENTER_CENTER,
+                name,
+                egui::FontId::proportional(13.0),
+                egui::Color32::from_rgb(110, 140, 200)
+            );
+        }
+    }
+}
+
+/// Screen position of a point in front of the camera for painting labels with egui
+pub fn project(camera: &Camera, camera_transform: &GlobalTransform, position: Vec3) -> Option<egui::Pos2> {
+    let height = camera.logical_viewport_size()?.y;
+    // viewport coordinates start at the bottom, egui's at the top
+    camera.world_to_viewport(camera_transform, position).map(|point| egui::pos2(point.x, height - point.y))
+}
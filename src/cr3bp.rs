@@ -0,0 +1,217 @@
+use bevy::math::{DMat4, DVec3, DVec4};
+
+// Circular restricted three-body problem in nondimensional synodic coordinates: the primaries sit at
+// (-mu, 0, 0) and (1 - mu, 0, 0), their distance, total mass and the frame's angular velocity are 1.
+
+fn distances(mu: f64, p: DVec3) -> (f64, f64) {
+    let r1 = (p - DVec3::new(-mu, 0.0, 0.0)).length();
+    let r2 = (p - DVec3::new(1.0 - mu, 0.0, 0.0)).length();
+    (r1, r2)
+}
+
+/// Effective (pseudo-)potential Ω of the rotating frame
+pub fn effective_potential(mu: f64, p: DVec3) -> f64 {
+    let (r1, r2) = distances(mu, p);
+    0.5 * (p.x * p.x + p.y * p.y) + (1.0 - mu) / r1 + mu / r2
+}
+
+/// Gradient of the effective potential
+pub fn potential_gradient(mu: f64, p: DVec3) -> DVec3 {
+    let (r1, r2) = distances(mu, p);
+    let k1 = (1.0 - mu) / r1.powi(3);
+    let k2 = mu / r2.powi(3);
+    DVec3::new(
+        p.x - k1 * (p.x + mu) - k2 * (p.x - 1.0 + mu),
+        p.y - k1 * p.y - k2 * p.y,
+        -k1 * p.z - k2 * p.z
+    )
+}
+
+/// Jacobi constant C = 2Ω - v², conserved along every trajectory of the restricted problem
+pub fn jacobi_constant(mu: f64, p: DVec3, v: DVec3) -> f64 {
+    2.0 * effective_potential(mu, p) - v.length_squared()
+}
+
+/// Acceleration in the rotating frame including the Coriolis term
+pub fn acceleration(mu: f64, p: DVec3, v: DVec3) -> DVec3 {
+    potential_gradient(mu, p) + DVec3::new(2.0 * v.y, -2.0 * v.x, 0.0)
+}
+
+/// x coordinate of the collinear Lagrange point L1, L2 or L3
+pub fn collinear_point(mu: f64, point: u8) -> f64 {
+    let hill = (mu / 3.0).cbrt();
+    let mut x = match point {
+        1 => 1.0 - mu - hill,
+        2 => 1.0 - mu + hill,
+        _ => -1.0 - 5.0 * mu / 12.0
+    };
+    for _ in 0..50 {
+        let r1 = (x + mu).abs();
+        let r2 = (x - 1.0 + mu).abs();
+        let f = x - (1.0 - mu) * (x + mu) / r1.powi(3) - mu * (x - 1.0 + mu) / r2.powi(3);
+        let df = 1.0 + 2.0 * (1.0 - mu) / r1.powi(3) + 2.0 * mu / r2.powi(3);
+        let step = f / df;
+        x -= step;
+        if step.abs() < 1e-15 {
+            break;
+        }
+    }
+    x
+}
+
+/// Planar state (x, y, vx, vy) together with its state transition matrix
+#[derive(Clone, Copy)]
+pub struct PlanarState {
+    pub state: DVec4,
+    pub stm: DMat4,
+}
+
+impl PlanarState {
+
+    pub fn new(state: DVec4) -> Self {
+        Self {
+            state,
+            stm: DMat4::IDENTITY
+        }
+    }
+
+    pub fn position(&self) -> DVec3 {
+        DVec3::new(self.state.x, self.state.y, 0.0)
+    }
+
+}
+
+fn planar_derivative(mu: f64, state: DVec4) -> DVec4 {
+    let p = DVec3::new(state.x, state.y, 0.0);
+    let a = acceleration(mu, p, DVec3::new(state.z, state.w, 0.0));
+    DVec4::new(state.z, state.w, a.x, a.y)
+}
+
+/// Jacobian of the planar equations of motion, used for the variational equations
+fn planar_jacobian(mu: f64, state: DVec4) -> DMat4 {
+    let (x, y) = (state.x, state.y);
+    let (r1, r2) = distances(mu, DVec3::new(x, y, 0.0));
+    let (d1, d2) = (x + mu, x - 1.0 + mu);
+    let (k1, k2) = ((1.0 - mu) / r1.powi(3), mu / r2.powi(3));
+    let (l1, l2) = (3.0 * (1.0 - mu) / r1.powi(5), 3.0 * mu / r2.powi(5));
+    let uxx = 1.0 - k1 - k2 + l1 * d1 * d1 + l2 * d2 * d2;
+    let uyy = 1.0 - k1 - k2 + l1 * y * y + l2 * y * y;
+    let uxy = l1 * d1 * y + l2 * d2 * y;
+    // column major: column j holds the derivatives with respect to state component j
+    DMat4::from_cols(
+        DVec4::new(0.0, 0.0, uxx, uxy),
+        DVec4::new(0.0, 0.0, uxy, uyy),
+        DVec4::new(1.0, 0.0, 0.0, -2.0),
+        DVec4::new(0.0, 1.0, 2.0, 0.0)
+    )
+}
+
+/// Advances a planar state and its state transition matrix by one RK4 step
+pub fn planar_step(mu: f64, s: &PlanarState, dt: f64) -> PlanarState {
+    let derivative = |state: DVec4, stm: DMat4| (planar_derivative(mu, state), planar_jacobian(mu, state) * stm);
+    let (k1, m1) = derivative(s.state, s.stm);
+    let (k2, m2) = derivative(s.state + k1 * (dt / 2.0), s.stm + m1 * (dt / 2.0));
+    let (k3, m3) = derivative(s.state + k2 * (dt / 2.0), s.stm + m2 * (dt / 2.0));
+    let (k4, m4) = derivative(s.state + k3 * dt, s.stm + m3 * dt);
+    PlanarState {
+        state: s.state + (k1 + k2 * 2.0 + k3 * 2.0 + k4) * (dt / 6.0),
+        stm: s.stm + (m1 + m2 * 2.0 + m3 * 2.0 + m4) * (dt / 6.0)
+    }
+}
+
+/// Integrates until the trajectory crosses the x axis again, returns the state at the crossing and its time
+fn propagate_to_x_axis(mu: f64, start: PlanarState, dt: f64) -> Option<(PlanarState, f64)> {
+    let mut s = start;
+    let mut t = 0.0;
+    for _ in 0..200_000 {
+        let next = planar_step(mu, &s, dt);
+        t += dt;
+        if t > 10.0 * dt && next.state.y.signum() != s.state.y.signum() && next.state.y != 0.0 {
+            // a few Newton iterations on the crossing time
+            let mut crossing = next;
+            let mut t_crossing = t;
+            for _ in 0..3 {
+                let step = -crossing.state.y / crossing.state.w;
+                crossing = planar_step(mu, &crossing, step);
+                t_crossing += step;
+            }
+            return Some((crossing, t_crossing));
+        }
+        s = next;
+    }
+    None
+}
+
+/// Planar Lyapunov orbit around a collinear point with its period and monodromy matrix
+pub struct LyapunovOrbit {
+    pub initial: DVec4,
+    pub period: f64,
+    pub monodromy: DMat4,
+}
+
+/// Finds a planar Lyapunov orbit around L1/L2/L3 with the x amplitude `amplitude` by differential correction,
+/// starting from the linearized solution and continuing in small amplitude steps.
+pub fn lyapunov_orbit(mu: f64, point: u8, amplitude: f64) -> Option<LyapunovOrbit> {
+    let x_l = collinear_point(mu, point);
+    let c2 = (1.0 - mu) / (x_l + mu).abs().powi(3) + mu / (x_l - 1.0 + mu).abs().powi(3);
+    let lambda = ((2.0 - c2 + (9.0 * c2 * c2 - 8.0 * c2).sqrt()) / 2.0).sqrt();
+    let k = (lambda * lambda + 1.0 + 2.0 * c2) / (2.0 * lambda);
+    let dt = 2.0 * std::f64::consts::PI / lambda / 2000.0;
+
+    let steps = (amplitude / 1e-4).ceil().max(1.0) as usize;
+    let mut velocity_ratio = -k * lambda;
+    let mut initial = DVec4::ZERO;
+    let mut half_period = 0.0;
+    for i in 1..=steps {
+        let ax = amplitude * i as f64 / steps as f64;
+        initial = DVec4::new(x_l - ax, 0.0, 0.0, -velocity_ratio * ax);
+        let mut converged = false;
+        for _ in 0..30 {
+            let (crossing, t) = propagate_to_x_axis(mu, PlanarState::new(initial), dt)?;
+            half_period = t;
+            let vx = crossing.state.z;
+            if vx.abs() < 1e-11 {
+                converged = true;
+                break;
+            }
+            let ax_crossing = planar_derivative(mu, crossing.state).z;
+            let phi = |row: usize, col: usize| crossing.stm.col(col)[row];
+            let correction = vx / (phi(2, 3) - ax_crossing / crossing.state.w * phi(1, 3));
+            initial.w -= correction;
+        }
+        if !converged {
+            return None;
+        }
+        velocity_ratio = -initial.w / ax;
+    }
+
+    let period = 2.0 * half_period;
+    let steps = 4000;
+    let mut s = PlanarState::new(initial);
+    for _ in 0..steps {
+        s = planar_step(mu, &s, period / steps as f64);
+    }
+    Some(LyapunovOrbit {
+        initial,
+        period,
+        monodromy: s.stm
+    })
+}
+
+/// Dominant eigenvector of a matrix by power iteration
+pub fn dominant_eigenvector(matrix: DMat4) -> DVec4 {
+    let mut v = DVec4::new(1.0, 0.5, 0.25, 0.125);
+    for _ in 0..100 {
+        v = (matrix * v).normalize();
+    }
+    v
+}
+
+/// Advances a planar state by one RK4 step without the state transition matrix
+pub fn planar_rk4(mu: f64, state: DVec4, dt: f64) -> DVec4 {
+    let k1 = planar_derivative(mu, state);
+    let k2 = planar_derivative(mu, state + k1 * (dt / 2.0));
+    let k3 = planar_derivative(mu, state + k2 * (dt / 2.0));
+    let k4 = planar_derivative(mu, state + k3 * dt);
+    state + (k1 + k2 * 2.0 + k3 * 2.0 + k4) * (dt / 6.0)
+}
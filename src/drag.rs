@@ -0,0 +1,147 @@
+use bevy::prelude::*;
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+
+use crate::{astro::radec_to_ecliptic, bodies::{ACCELERATION_TO_UNIT, KM_TO_UNIT}, body::{Gravity, Mass, Radius, Velocity}, measurement::Measurement, satellites::{update_satellites, LocalOrbit}, ui::SimTime, SimState};
+
+/// Shortest measurement before a decay rate is reported
+const MINIMUM_MEASUREMENT_DAYS: f32 = 1.0;
+
+pub struct DragPlugin;
+
+impl Plugin for DragPlugin {
+
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<AtmosphericDrag>()
+        .register_inspectable::<AtmosphericDrag>()
+        .register_inspectable::<Atmosphere>()
+        .register_inspectable::<Drag>()
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(measure_decay.after(update_satellites)));
+    }
+
+}
+
+/// Toggles the drag of all [Atmosphere]s on bodies with a [Drag] component
+#[derive(Resource, Inspectable)]
+pub struct AtmosphericDrag {
+    pub enabled: bool,
+}
+
+impl Default for AtmosphericDrag {
+
+    fn default() -> Self {
+        Self {
+            enabled: true
+        }
+    }
+
+}
+
+/// Exponential atmosphere: ρ = ρ₀ exp(-(h - h₀) / H), rotating with the planet
+#[derive(Component, Inspectable, Clone, Copy)]
+pub struct Atmosphere {
+    /// Altitude h₀ of the reference density in km
+    pub reference_altitude: f32,
+    /// Density ρ₀ at the reference altitude in kg/m³
+    pub reference_density: f32,
+    /// Scale height H in km
+    pub scale_height: f32,
+    /// No drag above this altitude in km
+    pub top: f32,
+    /// Angular velocity of the planet's rotation in rad/day
+    pub rotation: Vec3,
+}
+
+impl Atmosphere {
+
+    /// Atmosphere rotating once per sidereal `day` (in days) around the pole given by its right ascension and declination in degrees
+    pub fn new(reference_altitude: f32, reference_density: f32, scale_height: f32, top: f32, day: f32, pole_ra: f32, pole_dec: f32) -> Self {
+        Self {
+            reference_altitude,
+            reference_density,
+            scale_height,
+            top,
+            rotation: radec_to_ecliptic(pole_ra, pole_dec) * (std::f32::consts::TAU / day)
+        }
+    }
+
+    /// Density in kg/m³ at `altitude` km above the surface
+    pub fn density(&self, altitude: f32) -> f32 {
+        if altitude > self.top {
+            0.0
+        } else {
+            self.reference_density * (-(altitude - self.reference_altitude) / self.scale_height).exp()
+        }
+    }
+
+    /// Drag acceleration on a body at `r` with velocity `v` relative to the planet with the surface at `radius`
+    pub fn acceleration(&self, drag: &Drag, radius: f32, r: Vec3, v: Vec3) -> Vec3 {
+        let altitude = (r.length() - radius) / KM_TO_UNIT;
+        let density = self.density(altitude);
+        if density <= 0.0 || drag.ballistic_coefficient <= 0.0 {
+            return Vec3::ZERO;
+        }
+        // the air moves along with the surface
+        let airspeed = v - self.rotation.cross(r);
+        let speed = airspeed.length() / KM_TO_UNIT * 1000.0 / 86400.0;
+        -airspeed.normalize_or_zero() * (0.5 * density * speed * speed / drag.ballistic_coefficient) * ACCELERATION_TO_UNIT
+    }
+
+}
+
+/// Drag properties of a satellite
+#[derive(Component, Inspectable, Clone, Copy)]
+pub struct Drag {
+    /// Mass per drag area m / (C_D A) in kg/m²
+    pub ballistic_coefficient: f32,
+}
+
+/// Tracks the mean altitude of a satellite in an atmosphere and how fast it sinks
+#[derive(Component, Default)]
+pub struct Decay {
+    /// Semi-major axis minus the planet's radius in km
+    pub mean_altitude: Option<f32>,
+    /// Change of the semi-major axis in km/day
+    pub semi_major_axis: Measurement<f32>,
+}
+
+fn measure_decay(
+    g: Res<Gravity>,
+    sim_time: Res<SimTime>,
+    planets: Query<(Entity, &Transform, &Velocity, &Mass, &Radius), With<Atmosphere>>,
+    mut satellites: Query<(&Transform, &Velocity, &mut Decay, Option<&LocalOrbit>), Without<Atmosphere>>
+) {
+    for (transform, velocity, mut decay, local) in satellites.iter_mut() {
+        // satellites know their parent, everything else is assumed to orbit the closest planet with an atmosphere
+        let planet = match local {
+            Some(local) => planets.get(local.parent).ok(),
+            None => planets.iter().min_by(|a, b| {
+                a.1.translation.distance_squared(transform.translation).total_cmp(&b.1.translation.distance_squared(transform.translation))
+            })
+        };
+        let (_, planet_transform, planet_velocity, mass, radius) = match planet {
+            Some(planet) => planet,
+            None => continue
+        };
+        let (r, v) = match local {
+            Some(local) => (local.position.length(), local.velocity),
+            None => (
+                (transform.translation - planet_transform.translation).length() as f64,
+                (velocity.0 - planet_velocity.0).as_dvec3()
+            )
+        };
+        let gm = (g.0 * mass.0) as f64;
+        let energy = 2.0 / r - v.length_squared() / gm;
+        if r <= 0.0 || energy <= 0.0 {
+            // not bound to the planet
+            decay.mean_altitude = None;
+            decay.semi_major_axis.restart();
+            continue;
+        }
+        let semi_major_axis = (1.0 / energy) as f32;
+        decay.mean_altitude = Some((semi_major_axis - radius.0) / KM_TO_UNIT);
+        decay.semi_major_axis.update(semi_major_axis, sim_time.0, MINIMUM_MEASUREMENT_DAYS, |reference, elapsed| {
+            (semi_major_axis - reference) / KM_TO_UNIT / elapsed
+        });
+    }
+}
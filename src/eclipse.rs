@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+
+use crate::{body::{update_bodies, Radius, Sun}, lagrange::LagrangePoint, minor_bodies::MinorBody, satellites::LocalOrbit, ui::SimTime, SimState};
+
+pub struct EclipsePlugin;
+
+impl Plugin for EclipsePlugin {
+
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<Eclipses>()
+        .register_inspectable::<Eclipses>()
+        .add_event::<EclipseEvent>()
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(detect_eclipses.after(update_bodies)));
+    }
+
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Shadow {
+    Sunlit,
+    /// Part of the solar disk is covered
+    Penumbra,
+    /// The occulter is entirely inside the solar disk
+    Antumbra,
+    Umbra,
+}
+
+/// Tracks which bodies are in the shadow of which other body and reports changes as [EclipseEvent]s
+#[derive(Resource, Inspectable, Default)]
+pub struct Eclipses {
+    pub enabled: bool,
+    #[inspectable(ignore)]
+    shadows: HashMap<(Entity, Entity), Shadow>,
+}
+
+impl Eclipses {
+
+    /// Forgets the shadows of the bodies of the last run
+    pub fn clear(&mut self) {
+        self.shadows.clear();
+    }
+
+}
+
+/// A body entering (`started`) or leaving a shadow of the occulter
+pub struct EclipseEvent {
+    pub body: String,
+    pub occulter: String,
+    pub shadow: Shadow,
+    pub started: bool,
+    /// Simulation time in days
+    pub time: f32,
+}
+
+/// Apparent radii of the Sun and the occulter and the separation of their centers as seen from `observer`,
+/// `None` if the occulter is not between the observer and the Sun
+fn disks(observer: Vec3, sun: (Vec3, f32), occulter: (Vec3, f32)) -> Option<(f32, f32, f32)> {
+    let to_sun = sun.0 - observer;
+    let to_occulter = occulter.0 - observer;
+    let (sun_distance, occulter_distance) = (to_sun.length(), to_occulter.length());
+    if occulter_distance >= sun_distance || occulter_distance <= occulter.1 || sun_distance <= sun.1 {
+        return None;
+    }
+    // atan2 keeps small angles accurate in f32
+    let a = (sun.1 / sun_distance).asin();
+    let b = (occulter.1 / occulter_distance).asin();
+    let c = to_sun.cross(to_occulter).length().atan2(to_sun.dot(to_occulter));
+    Some((a, b, c))
+}
+
+/// Fraction of the solar disk visible from `observer` (1 = full sunlight, 0 = umbra),
+/// with the Sun and the occulting body given as center and radius
+pub fn illumination(observer: Vec3, sun: (Vec3, f32), occulter: (Vec3, f32)) -> f32 {
+    let (a, b, c) = match disks(observer, sun, occulter) {
+        Some(disks) => disks,
+        None => return 1.0
+    };
+    if c >= a + b {
+        1.0
+    } else if c <= b - a {
+        0.0
+    } else if c <= a - b {
+        // annular: the occulter is entirely inside the solar disk
+        1.0 - (b * b) / (a * a)
+    } else {
+        let overlap = a * a * ((c * c + a * a - b * b) / (2.0 * c * a)).clamp(-1.0, 1.0).acos()
+            + b * b * ((c * c + b * b - a * a) / (2.0 * c * b)).clamp(-1.0, 1.0).acos()
+            - 0.5 * ((-c + a + b) * (c + a - b) * (c - a + b) * (c + a + b)).max(0.0).sqrt();
+        (1.0 - overlap / (std::f32::consts::PI * a * a)).clamp(0.0, 1.0)
+    }
+}
+
+/// Which part of the occulter's shadow `observer` is in
+pub fn shadow(observer: Vec3, sun: (Vec3, f32), occulter: (Vec3, f32)) -> Shadow {
+    match disks(observer, sun, occulter) {
+        Some((a, b, c)) if c < a + b => {
+            if c <= b - a {
+                Shadow::Umbra
+            } else if c <= a - b {
+                Shadow::Antumbra
+            } else {
+                Shadow::Penumbra
+            }
+        },
+        _ => Shadow::Sunlit
+    }
+}
+
+/// Checks every body against every other body with a radius. Satellites on a [LocalOrbit] are placed at their
+/// true distance from the parent instead of the enlarged display distance, and positions are taken relative to
+/// the occulter to keep the precision of low orbits.
+fn detect_eclipses(
+    mut settings: ResMut<Eclipses>,
+    mut events: EventWriter<EclipseEvent>,
+    sim_time: Res<SimTime>,
+    sun: Query<(&Transform, &Radius), With<Sun>>,
+    bodies: Query<(Entity, &Name, &Transform, &Radius, Option<&LocalOrbit>), (Without<Sun>, Without<LagrangePoint>, Without<MinorBody>)>
+) {
+    if !settings.enabled {
+        settings.clear();
+        return;
+    }
+    let (sun, sun_radius) = match sun.get_single() {
+        Ok((transform, radius)) => (transform.translation, radius.0),
+        Err(_) => return
+    };
+    let mut parents: HashMap<Entity, Vec3> = HashMap::new();
+    for (entity, _, transform, _, _) in bodies.iter() {
+        parents.insert(entity, transform.translation);
+    }
+    // true position and, for satellites, the parent and the position relative to it
+    let position = |transform: &Transform, local: Option<&LocalOrbit>| match local.and_then(|local| parents.get(&local.parent).map(|parent| (local, *parent))) {
+        Some((local, parent)) => (parent + local.position.as_vec3(), Some((local.parent, local.position.as_vec3()))),
+        None => (transform.translation, None)
+    };
+    for (entity, name, transform, _, local) in bodies.iter() {
+        let (observer, relative) = position(transform, local);
+        for (occulter_entity, occulter_name, occulter_transform, radius, occulter_local) in bodies.iter() {
+            if occulter_entity == entity || radius.0 <= 0.0 {
+                continue;
+            }
+            let (occulter, _) = position(occulter_transform, occulter_local);
+            let offset = match relative {
+                Some((parent, offset)) if parent == occulter_entity => offset,
+                _ => observer - occulter
+            };
+            let new = shadow(offset, (sun - occulter, sun_radius), (Vec3::ZERO, radius.0));
+            let old = settings.shadows.insert((entity, occulter_entity), new).unwrap_or(Shadow::Sunlit);
+            if new == old {
+                continue;
+            }
+            let mut send = |shadow: Shadow, started: bool| events.send(EclipseEvent {
+                body: name.to_string(),
+                occulter: occulter_name.to_string(),
+                shadow,
+                started,
+                time: sim_time.0
+            });
+            if old != Shadow::Sunlit {
+                send(old, false);
+            }
+            if new != Shadow::Sunlit {
+                send(new, true);
+            }
+        }
+    }
+}
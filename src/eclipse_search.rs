@@ -0,0 +1,358 @@
+use bevy::{prelude::*, math::DVec3};
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+
+use crate::{astro::{julian_date, sim_date}, bodies::AU_TO_UNIT_SCALE, body::{Gravity, Kinematic, Mass, Radius, Velocity}, jwst::{rest_at, JWST}, kepler::OrbitalElements, lagrange::LagrangePoint, minor_bodies::MinorBody, satellites::LocalOrbit, ui::SimTime, SimState};
+
+/// Integration step of the search in days
+const STEP: f64 = 1.0 / 24.0;
+/// Step in days around each minimum found at [STEP], to time and classify the eclipse
+const FINE_STEP: f64 = 1.0 / 1440.0;
+/// Longest an eclipse is followed from its greatest phase to a contact, in days
+const MAX_HALF_DURATION: f64 = 0.5;
+
+pub struct EclipseSearchPlugin;
+
+impl Plugin for EclipseSearchPlugin {
+
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<EclipseSearch>()
+        .register_inspectable::<EclipseSearch>()
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(search_eclipses))
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(jump_to_eclipse));
+    }
+
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EclipseType {
+    SolarTotal,
+    SolarAnnular,
+    SolarPartial,
+    LunarTotal,
+    LunarPartial,
+    LunarPenumbral,
+}
+
+impl EclipseType {
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EclipseType::SolarTotal => "Total solar",
+            EclipseType::SolarAnnular => "Annular solar",
+            EclipseType::SolarPartial => "Partial solar",
+            EclipseType::LunarTotal => "Total lunar",
+            EclipseType::LunarPartial => "Partial lunar",
+            EclipseType::LunarPenumbral => "Penumbral lunar"
+        }
+    }
+
+}
+
+pub struct PredictedEclipse {
+    pub kind: EclipseType,
+    /// Simulation time of the greatest eclipse in days
+    pub time: f32,
+    /// Simulation times of the first and last contact with the penumbra in days
+    pub contacts: (f32, f32),
+}
+
+/// Copy of the massive bodies the search integrates, kept to move the simulation to a found eclipse
+struct Snapshot {
+    time: f64,
+    entities: Vec<Entity>,
+    bodies: Vec<(DVec3, DVec3, f64)>,
+}
+
+/// Searches upcoming eclipses by integrating a copy of the bodies ahead with point mass gravity only.
+/// The UI sets `search` or `jump` and the systems pick the request up in the next frame.
+#[derive(Resource, Inspectable)]
+pub struct EclipseSearch {
+    pub show: bool,
+    /// Days to search ahead
+    pub days: f32,
+    #[inspectable(ignore)]
+    pub search: bool,
+    /// Index of the eclipse in `results` to move the simulation to
+    #[inspectable(ignore)]
+    pub jump: Option<usize>,
+    #[inspectable(ignore)]
+    pub results: Vec<PredictedEclipse>,
+    #[inspectable(ignore)]
+    snapshot: Option<Snapshot>,
+}
+
+impl Default for EclipseSearch {
+
+    fn default() -> Self {
+        Self {
+            show: false,
+            days: 730.0,
+            search: false,
+            jump: None,
+            results: Vec::new(),
+            snapshot: None
+        }
+    }
+
+}
+
+impl EclipseSearch {
+
+    /// Drops the results and the snapshot, they refer to the entities of the last run
+    pub fn clear(&mut self) {
+        self.search = false;
+        self.jump = None;
+        self.results.clear();
+        self.snapshot = None;
+    }
+
+}
+
+fn accelerations(g: f64, bodies: &[(DVec3, DVec3, f64)]) -> Vec<DVec3> {
+    let mut acc = vec![DVec3::ZERO; bodies.len()];
+    for i in 0..bodies.len() {
+        for j in (i + 1)..bodies.len() {
+            let d = bodies[j].0 - bodies[i].0;
+            let f = d * (g / d.length().powi(3));
+            acc[i] += f * bodies[j].2;
+            acc[j] -= f * bodies[i].2;
+        }
+    }
+    acc
+}
+
+/// Kick-drift-kick leapfrog step
+fn step(g: f64, bodies: &mut [(DVec3, DVec3, f64)], dt: f64) {
+    for (body, a) in bodies.iter_mut().zip(accelerations(g, bodies)) {
+        body.1 += a * (dt / 2.0);
+        body.0 += body.1 * dt;
+    }
+    for (body, a) in bodies.iter_mut().zip(accelerations(g, bodies)) {
+        body.1 += a * (dt / 2.0);
+    }
+}
+
+/// Distance of `body` from the axis of the shadow `occulter` casts away from `sun`, the distance behind the
+/// occulter along the axis and the distance between the Sun and the occulter
+fn shadow_axis(sun: DVec3, occulter: DVec3, body: DVec3) -> (f64, f64, f64) {
+    let axis = occulter - sun;
+    let distance = axis.length();
+    let axis = axis / distance;
+    let along = (body - occulter).dot(axis);
+    ((body - occulter - axis * along).length(), along, distance)
+}
+
+/// Radii of the umbra (negative past its apex, where it becomes the antumbra) and the penumbra `along` behind an
+/// occulter at `distance` from the Sun
+fn shadow_radii(sun_radius: f64, occulter_radius: f64, along: f64, distance: f64) -> (f64, f64) {
+    (
+        occulter_radius - along * (sun_radius - occulter_radius) / distance,
+        occulter_radius + along * (sun_radius + occulter_radius) / distance
+    )
+}
+
+/// The Moon's shadow on Earth, `offset` is the distance of Earth's center from the shadow axis
+fn solar_eclipse(offset: f64, along: f64, distance: f64, radii: (f64, f64, f64)) -> Option<EclipseType> {
+    let (sun, earth, moon) = radii;
+    if along <= 0.0 {
+        return None;
+    }
+    let (umbra, penumbra) = shadow_radii(sun, moon, along, distance);
+    if offset < earth + umbra.abs() {
+        Some(if umbra > 0.0 { EclipseType::SolarTotal } else { EclipseType::SolarAnnular })
+    } else if offset < earth + penumbra {
+        Some(EclipseType::SolarPartial)
+    } else {
+        None
+    }
+}
+
+/// The Moon in Earth's shadow, `offset` is the distance of the Moon's center from the shadow axis
+fn lunar_eclipse(offset: f64, along: f64, distance: f64, radii: (f64, f64, f64)) -> Option<EclipseType> {
+    let (sun, earth, moon) = radii;
+    if along <= 0.0 {
+        return None;
+    }
+    let (umbra, penumbra) = shadow_radii(sun, earth, along, distance);
+    if offset + moon < umbra {
+        Some(EclipseType::LunarTotal)
+    } else if offset - moon < umbra {
+        Some(EclipseType::LunarPartial)
+    } else if offset - moon < penumbra {
+        Some(EclipseType::LunarPenumbral)
+    } else {
+        None
+    }
+}
+
+/// Distance of the shadowed body from the shadow axis and the eclipse there, for the Moon's shadow on Earth or
+/// the Moon in Earth's shadow (`lunar`)
+fn sample(state: &[(DVec3, DVec3, f64)], bodies: (usize, usize, usize), radii: (f64, f64, f64), lunar: bool) -> (f64, Option<EclipseType>) {
+    let (sun, earth, moon) = (state[bodies.0].0, state[bodies.1].0, state[bodies.2].0);
+    if lunar {
+        let (offset, along, distance) = shadow_axis(sun, earth, moon);
+        (offset, lunar_eclipse(offset, along, distance, radii))
+    } else {
+        let (offset, along, distance) = shadow_axis(sun, moon, earth);
+        (offset, solar_eclipse(offset, along, distance, radii))
+    }
+}
+
+/// Integrates `state` at `time` across the two steps around a minimum of the distance from the shadow axis at
+/// [FINE_STEP], classifies the eclipse at the closest approach and follows it both ways to the contacts. A short
+/// or grazing eclipse can fall between the samples of the search, so every minimum is refined, eclipse or not.
+fn refine(g: f64, mut state: Vec<(DVec3, DVec3, f64)>, time: f64, bodies: (usize, usize, usize), radii: (f64, f64, f64), lunar: bool) -> Option<PredictedEclipse> {
+    let mut closest = (f64::MAX, 0.0, state.clone());
+    let steps = (2.0 * STEP / FINE_STEP).round() as usize;
+    for n in 0..=steps {
+        let offset = sample(&state, bodies, radii, lunar).0;
+        if offset < closest.0 {
+            closest = (offset, n as f64 * FINE_STEP, state.clone());
+        }
+        step(g, &mut state, FINE_STEP);
+    }
+    let (_, greatest, state) = closest;
+    let kind = sample(&state, bodies, radii, lunar).1?;
+    // leapfrog runs backwards with a negative step
+    let contact = |dt: f64| {
+        let mut state = state.clone();
+        let mut elapsed = 0.0;
+        while elapsed < MAX_HALF_DURATION && sample(&state, bodies, radii, lunar).1.is_some() {
+            step(g, &mut state, dt);
+            elapsed += FINE_STEP;
+        }
+        time + greatest + elapsed.copysign(dt)
+    };
+    Some(PredictedEclipse {
+        kind,
+        time: (time + greatest) as f32,
+        contacts: (contact(-FINE_STEP) as f32, contact(FINE_STEP) as f32)
+    })
+}
+
+fn search_eclipses(
+    mut search: ResMut<EclipseSearch>,
+    g: Res<Gravity>,
+    sim_time: Res<SimTime>,
+    bodies: Query<(Entity, &Name, &Mass, &Transform, &Velocity, Option<&Radius>), (Without<Kinematic>, Without<MinorBody>)>
+) {
+    if !search.search {
+        return;
+    }
+    search.search = false;
+    search.results.clear();
+    let find = |name: &str| bodies.iter().position(|(_, body, _, _, _, _)| body.as_str() == name);
+    let indices = match (find("Sun"), find("Earth"), find("Moon")) {
+        (Some(sun), Some(earth), Some(moon)) => (sun, earth, moon),
+        _ => {
+            warn!("The eclipse search needs the Sun, Earth and the Moon");
+            return;
+        }
+    };
+    let radius = |index: usize| bodies.iter().nth(index).and_then(|(_, _, _, _, _, radius)| radius).map_or(0.0, |radius| radius.0 as f64);
+    let radii = (radius(indices.0), radius(indices.1), radius(indices.2));
+    let snapshot = Snapshot {
+        time: sim_time.0 as f64,
+        entities: bodies.iter().map(|(entity, _, _, _, _, _)| entity).collect(),
+        bodies: bodies.iter().map(|(_, _, mass, transform, velocity, _)| (transform.translation.as_dvec3(), velocity.0.as_dvec3(), mass.0 as f64)).collect()
+    };
+    let g = g.0 as f64;
+    let mut state = snapshot.bodies.clone();
+    // states and distances from the shadow axes at the last three steps, a minimum in the middle may be an eclipse
+    let mut states: Vec<Vec<(DVec3, DVec3, f64)>> = Vec::new();
+    let mut solar: Vec<f64> = Vec::new();
+    let mut lunar: Vec<f64> = Vec::new();
+    let steps = (search.days as f64 / STEP).ceil() as usize;
+    for n in 0..=steps {
+        states.push(state.clone());
+        solar.push(sample(&state, indices, radii, false).0);
+        lunar.push(sample(&state, indices, radii, true).0);
+        if states.len() > 3 {
+            states.remove(0);
+            solar.remove(0);
+            lunar.remove(0);
+        }
+        for (offsets, is_lunar) in [(&solar, false), (&lunar, true)] {
+            if let [p0, p1, p2] = offsets.as_slice() {
+                if p1 <= p0 && p1 < p2 {
+                    let time = snapshot.time + (n as f64 - 2.0) * STEP;
+                    search.results.extend(refine(g, states[0].clone(), time, indices, radii, is_lunar));
+                }
+            }
+        }
+        step(g, &mut state, STEP);
+    }
+    search.results.sort_by(|a, b| a.time.total_cmp(&b.time));
+    search.snapshot = Some(snapshot);
+}
+
+/// Integrates the snapshot of the last search to the selected eclipse and moves the bodies and the clock there.
+/// Bodies outside the snapshot are advanced separately: minor bodies are placed from their elements, satellites
+/// follow a Kepler orbit around their parent (SGP4 takes over again in the next frame where enabled) and JWST,
+/// unless it is on the scripted orbit, restarts at rest at L2.
+#[allow(clippy::too_many_arguments)]
+fn jump_to_eclipse(
+    mut search: ResMut<EclipseSearch>,
+    g: Res<Gravity>,
+    mut sim_time: ResMut<SimTime>,
+    mut bodies: Query<(&Name, &mut Transform, &mut Velocity, Option<&MinorBody>), Without<JWST>>,
+    mut satellites: Query<&mut LocalOrbit>,
+    masses: Query<&Mass>,
+    mut jwst: Query<(&mut Transform, &mut Velocity), (With<JWST>, Without<Kinematic>)>,
+    lagrange_points: Query<(&LagrangePoint, &Name)>
+) {
+    let index = match search.jump.take() {
+        Some(index) => index,
+        None => return
+    };
+    let (time, snapshot) = match (search.results.get(index), &search.snapshot) {
+        (Some(eclipse), Some(snapshot)) => (eclipse.time as f64, snapshot),
+        _ => return
+    };
+    let mut state = snapshot.bodies.clone();
+    let mut elapsed = 0.0;
+    while elapsed < time - snapshot.time {
+        let dt = STEP.min(time - snapshot.time - elapsed);
+        step(g.0 as f64, &mut state, dt);
+        elapsed += dt;
+    }
+    let (mut sun, mut earth) = (None, None);
+    for (entity, (position, velocity, _)) in snapshot.entities.iter().zip(state.iter()) {
+        if let Ok((name, mut transform, mut body_velocity, _)) = bodies.get_mut(*entity) {
+            transform.translation = position.as_vec3();
+            body_velocity.0 = velocity.as_vec3();
+            match name.as_str() {
+                "Sun" => sun = Some((transform.translation, body_velocity.0)),
+                "Earth" => earth = Some((transform.translation, body_velocity.0)),
+                _ => {}
+            }
+        }
+    }
+    // the elements are heliocentric
+    let (sun_position, sun_velocity) = sun.unwrap_or((Vec3::ZERO, Vec3::ZERO));
+    let date = julian_date(sim_date(time as f32));
+    for (_, mut transform, mut velocity, minor_body) in bodies.iter_mut() {
+        if let Some(minor_body) = minor_body {
+            let (position, body_velocity) = minor_body.elements.state(date);
+            transform.translation = sun_position + position.as_vec3() * AU_TO_UNIT_SCALE;
+            velocity.0 = sun_velocity + body_velocity.as_vec3() * AU_TO_UNIT_SCALE;
+        }
+    }
+    // the local states are still at the current time
+    let elapsed = time - sim_time.0 as f64;
+    for mut orbit in satellites.iter_mut() {
+        let mu = masses.get(orbit.parent).map_or(0.0, |mass| (g.0 * mass.0) as f64);
+        if mu > 0.0 {
+            let elements = OrbitalElements::from_state(mu, orbit.position, orbit.velocity, 0.0);
+            (orbit.position, orbit.velocity) = elements.state_around(mu, elapsed);
+        }
+    }
+    let l2 = lagrange_points.iter().find(|(_, name)| name.as_str() == "SE-L2").map(|(point, _)| point);
+    if let (Some(sun), Some(earth), Some(l2)) = (sun, earth, l2) {
+        for (mut transform, mut velocity) in jwst.iter_mut() {
+            (transform.translation, velocity.0) = rest_at(l2, sun, earth);
+        }
+    }
+    sim_time.0 = time as f32;
+}
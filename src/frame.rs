@@ -0,0 +1,149 @@
+use bevy::{prelude::*, math::DVec3};
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+
+use crate::{body::{update_bodies, body_focus, Mass, Velocity}, camera::PanOrbitCamera, SimState};
+
+pub struct FramePlugin;
+
+impl Plugin for FramePlugin {
+
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<RotatingFrame>()
+        .register_inspectable::<RotatingFrame>()
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(update_rotating_frame.after(update_bodies)))
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(follow_rotating_frame.after(update_rotating_frame).before(body_focus)));
+    }
+
+}
+
+/// Co-rotating frame of two primaries (e.g. Sun and Earth). The x axis points from the
+/// primary to the secondary, the z axis along their orbital angular momentum.
+#[derive(Resource, Inspectable)]
+pub struct RotatingFrame {
+    pub enabled: bool,
+    pub primary: String,
+    pub secondary: String,
+    /// Barycenter of the two primaries
+    pub center: Vec3,
+    /// Velocity of the barycenter in units/day
+    pub velocity: Vec3,
+    pub orientation: Quat,
+    /// Angular velocity of the frame in rad/day
+    pub angular_velocity: f32,
+    /// Distance between the primaries in units
+    pub distance: f32,
+    /// Mass ratio m2 / (m1 + m2)
+    pub mu: f32,
+}
+
+impl Default for RotatingFrame {
+
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            primary: "Sun".to_string(),
+            secondary: "Earth".to_string(),
+            center: Vec3::ZERO,
+            velocity: Vec3::ZERO,
+            orientation: Quat::IDENTITY,
+            angular_velocity: 0.0,
+            distance: 0.0,
+            mu: 0.0
+        }
+    }
+
+}
+
+impl RotatingFrame {
+
+    /// Converts a world position into the displayed frame
+    pub fn to_local(&self, world: Vec3) -> Vec3 {
+        if self.enabled {
+            self.orientation.inverse() * (world - self.center)
+        } else {
+            world
+        }
+    }
+
+    /// Converts a position of the displayed frame back into world space
+    pub fn to_world(&self, local: Vec3) -> Vec3 {
+        if self.enabled {
+            self.center + self.orientation * local
+        } else {
+            local
+        }
+    }
+
+    /// Transform that places meshes built in synodic coordinates into the world
+    pub fn synodic_transform(&self) -> Transform {
+        Transform {
+            translation: self.center,
+            rotation: self.orientation,
+            scale: Vec3::splat(self.distance)
+        }
+    }
+
+    /// Converts a world state into the nondimensional synodic coordinates used by [crate::cr3bp]
+    pub fn to_synodic(&self, pos: Vec3, vel: Vec3) -> (DVec3, DVec3) {
+        let inverse = self.orientation.inverse();
+        let local_pos = inverse * (pos - self.center);
+        let local_vel = inverse * (vel - self.velocity) - Vec3::Z.cross(local_pos) * self.angular_velocity;
+        (
+            local_pos.as_dvec3() / self.distance as f64,
+            local_vel.as_dvec3() / (self.distance * self.angular_velocity) as f64
+        )
+    }
+
+}
+
+pub fn update_rotating_frame(
+    mut frame: ResMut<RotatingFrame>,
+    bodies: Query<(&Name, &Mass, &Transform, &Velocity)>
+) {
+    let mut primary = None;
+    let mut secondary = None;
+    for (name, mass, transform, velocity) in bodies.iter() {
+        if name.as_str() == frame.primary {
+            primary = Some((mass.0, transform.translation, velocity.0));
+        } else if name.as_str() == frame.secondary {
+            secondary = Some((mass.0, transform.translation, velocity.0));
+        }
+    }
+    if let (Some((m1, p1, v1)), Some((m2, p2, v2))) = (primary, secondary) {
+        let r = p2 - p1;
+        let v = v2 - v1;
+        let h = r.cross(v);
+        if let (Some(x), Some(z)) = (r.try_normalize(), h.try_normalize()) {
+            let y = z.cross(x);
+            frame.center = (p1 * m1 + p2 * m2) / (m1 + m2);
+            frame.velocity = (v1 * m1 + v2 * m2) / (m1 + m2);
+            frame.orientation = Quat::from_mat3(&Mat3::from_cols(x, y, z));
+            frame.angular_velocity = h.length() / r.length_squared();
+            frame.distance = r.length();
+            frame.mu = m2 / (m1 + m2);
+        }
+    }
+}
+
+/// Carries the camera along with the rotating frame so that the primaries stay fixed on screen
+fn follow_rotating_frame(
+    frame: Res<RotatingFrame>,
+    mut last: Local<Option<(Vec3, Quat)>>,
+    mut camera: Query<(&mut PanOrbitCamera, &mut Transform)>
+) {
+    if !frame.enabled {
+        *last = None;
+        return;
+    }
+    if let Some((old_center, old_orientation)) = *last {
+        let delta = frame.orientation * old_orientation.inverse();
+        for (mut pan_orbit, mut transform) in camera.iter_mut() {
+            pan_orbit.focus = frame.center + delta * (pan_orbit.focus - old_center);
+            pan_orbit.old_focus = pan_orbit.focus;
+            transform.rotation = delta * transform.rotation;
+            transform.translation = pan_orbit.focus + transform.rotation * Vec3::new(0.0, 0.0, pan_orbit.radius);
+        }
+    }
+    *last = Some((frame.center, frame.orientation));
+}
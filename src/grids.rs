@@ -0,0 +1,217 @@
+use bevy::{prelude::*, render::{mesh::PrimitiveTopology, view::NoFrustumCulling}, pbr::NotShadowCaster};
+use bevy_egui::{egui, EguiContext};
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+
+use crate::{bodies::AU_TO_UNIT_SCALE, body::Sun, camera::PanOrbitCamera, constellations::project, lines::line_mesh, skybox::SkyFrame};
+
+/// Radius of the sphere the sky grids are drawn on, inside the constellations
+const SPHERE_RADIUS: f32 = 800.0;
+/// Radii of the circles of the ecliptic plane grid in AU
+const PLANE_CIRCLES: [f32; 7] = [1.0, 2.0, 5.0, 10.0, 20.0, 30.0, 40.0];
+
+pub struct GridPlugin;
+
+impl Plugin for GridPlugin {
+
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<Grids>()
+        .register_inspectable::<Grids>()
+        .add_startup_system(spawn_grids)
+        .add_system(update_grids)
+        .add_system(grid_labels);
+    }
+
+}
+
+/// Toggles of the reference grids
+#[derive(Resource, Inspectable, Default)]
+pub struct Grids {
+    pub ecliptic: bool,
+    pub equatorial: bool,
+    pub galactic: bool,
+    /// Circles and spokes in the ecliptic plane around the Sun
+    pub plane: bool,
+    pub labels: bool,
+}
+
+impl Grids {
+
+    fn shows(&self, frame: SkyFrame) -> bool {
+        match frame {
+            SkyFrame::Ecliptic => self.ecliptic,
+            SkyFrame::Equatorial => self.equatorial,
+            SkyFrame::Galactic => self.galactic
+        }
+    }
+
+}
+
+/// Longitude and latitude lines on the sky sphere, centered on the camera
+#[derive(Component)]
+pub struct SkyGrid(pub SkyFrame);
+
+/// Distance circles in the ecliptic plane, centered on the Sun
+#[derive(Component)]
+pub struct PlaneGrid;
+
+fn grid_color(frame: SkyFrame) -> Color {
+    match frame {
+        SkyFrame::Ecliptic => Color::rgb(0.6, 0.55, 0.2),
+        SkyFrame::Equatorial => Color::rgb(0.2, 0.5, 0.6),
+        SkyFrame::Galactic => Color::rgb(0.55, 0.25, 0.55)
+    }
+}
+
+/// Point on the unit sphere at a longitude and latitude in degrees, in the frame's own coordinates
+fn spherical(longitude: f32, latitude: f32) -> Vec3 {
+    let (longitude, latitude) = (longitude.to_radians(), latitude.to_radians());
+    Vec3::new(latitude.cos() * longitude.cos(), latitude.cos() * longitude.sin(), latitude.sin())
+}
+
+/// Meridians every 15° and parallels every 10° as line segments on a sphere
+pub fn sky_grid_mesh(radius: f32) -> Mesh {
+    let mut segments = Vec::new();
+    for meridian in (0..360).step_by(15) {
+        for latitude in (-88..88).step_by(2) {
+            segments.push(spherical(meridian as f32, latitude as f32) * radius);
+            segments.push(spherical(meridian as f32, latitude as f32 + 2.0) * radius);
+        }
+    }
+    for parallel in (-80..=80).step_by(10) {
+        for longitude in (0..360).step_by(2) {
+            segments.push(spherical(longitude as f32, parallel as f32) * radius);
+            segments.push(spherical(longitude as f32 + 2.0, parallel as f32) * radius);
+        }
+    }
+    line_mesh(PrimitiveTopology::LineList, segments)
+}
+
+/// Circles and spokes every 30° in the xy plane, the radii in AU
+pub fn plane_grid_mesh(circles: &[f32]) -> Mesh {
+    let mut segments = Vec::new();
+    for radius in circles {
+        for angle in (0..360).step_by(2) {
+            segments.push(spherical(angle as f32, 0.0) * *radius * AU_TO_UNIT_SCALE);
+            segments.push(spherical(angle as f32 + 2.0, 0.0) * *radius * AU_TO_UNIT_SCALE);
+        }
+    }
+    let outer = circles.iter().copied().fold(0.0, f32::max) * AU_TO_UNIT_SCALE;
+    for angle in (0..360).step_by(30) {
+        segments.push(Vec3::ZERO);
+        segments.push(spherical(angle as f32, 0.0) * outer);
+    }
+    line_mesh(PrimitiveTopology::LineList, segments)
+}
+
+fn spawn_grids(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>
+) {
+    let sky_mesh = meshes.add(sky_grid_mesh(SPHERE_RADIUS));
+    for frame in [SkyFrame::Ecliptic, SkyFrame::Equatorial, SkyFrame::Galactic] {
+        commands.spawn(MaterialMeshBundle {
+            mesh: sky_mesh.clone(),
+            material: materials.add(StandardMaterial {
+                base_color: grid_color(frame),
+                unlit: true,
+                ..default()
+            }),
+            transform: Transform::from_rotation(Quat::from_mat3(&frame.to_ecliptic())),
+            visibility: Visibility::INVISIBLE,
+            ..default()
+        })
+        .insert(NotShadowCaster)
+        .insert(NoFrustumCulling)
+        .insert(SkyGrid(frame));
+    }
+    commands.spawn(MaterialMeshBundle {
+        mesh: meshes.add(plane_grid_mesh(&PLANE_CIRCLES)),
+        material: materials.add(StandardMaterial {
+            base_color: Color::rgb(0.3, 0.3, 0.3),
+            unlit: true,
+            ..default()
+        }),
+        visibility: Visibility::INVISIBLE,
+        ..default()
+    })
+    .insert(NotShadowCaster)
+    .insert(NoFrustumCulling)
+    .insert(PlaneGrid);
+}
+
+fn update_grids(
+    settings: Res<Grids>,
+    camera: Query<&Transform, With<PanOrbitCamera>>,
+    sun: Query<&Transform, (With<Sun>, Without<SkyGrid>, Without<PlaneGrid>)>,
+    mut sky_grids: Query<(&SkyGrid, &mut Transform, &mut Visibility), (Without<PanOrbitCamera>, Without<PlaneGrid>)>,
+    mut plane_grid: Query<(&mut Transform, &mut Visibility), (With<PlaneGrid>, Without<PanOrbitCamera>, Without<Sun>)>
+) {
+    if let Ok(camera) = camera.get_single() {
+        for (grid, mut transform, mut visibility) in sky_grids.iter_mut() {
+            transform.translation = camera.translation;
+            visibility.is_visible = settings.shows(grid.0);
+        }
+    }
+    if let Ok((mut transform, mut visibility)) = plane_grid.get_single_mut() {
+        transform.translation = sun.get_single().map_or(Vec3::ZERO, |sun| sun.translation);
+        visibility.is_visible = settings.plane;
+    }
+}
+
+/// Labels the longitudes along the equator and the latitudes along the zero meridian of each visible grid,
+/// and the distances of the plane grid along the x axis
+fn grid_labels(
+    mut egui_context: ResMut<EguiContext>,
+    settings: Res<Grids>,
+    camera: Query<(&Camera, &GlobalTransform), With<PanOrbitCamera>>,
+    plane_grid: Query<&Transform, With<PlaneGrid>>
+) {
+    if !settings.labels {
+        return;
+    }
+    let (camera, camera_transform) = match camera.get_single() {
+        Ok(camera) => camera,
+        Err(_) => return
+    };
+    let painter = egui_context.ctx_mut().layer_painter(egui::LayerId::background());
+    let label = |position: Vec3, text: String, color: Color| {
+        if let Some(point) = project(camera, camera_transform, position) {
+            let [r, g, b, _] = color.as_rgba_f32();
+            painter.text(
+                point,
+                egui::Align2::LEFT_BOTTOM,
+                text,
+                egui::FontId::proportional(11.0),
+                egui::Color32::from_rgb((r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8)
+            );
+        }
+    };
+    for frame in [SkyFrame::Ecliptic, SkyFrame::Equatorial, SkyFrame::Galactic] {
+        if !settings.shows(frame) {
+            continue;
+        }
+        let to_ecliptic = frame.to_ecliptic();
+        let color = grid_color(frame);
+        let sky = |longitude: f32, latitude: f32| camera_transform.translation() + to_ecliptic * spherical(longitude, latitude) * SPHERE_RADIUS;
+        for longitude in (0..360).step_by(30) {
+            let text = match frame {
+                // right ascension in hours
+                SkyFrame::Equatorial => format!("{}h", longitude / 15),
+                _ => format!("{}°", longitude)
+            };
+            label(sky(longitude as f32, 0.0), text, color);
+        }
+        for latitude in (-60..=60).step_by(30).filter(|latitude| *latitude != 0) {
+            label(sky(0.0, latitude as f32), format!("{:+}°", latitude), color);
+        }
+    }
+    if settings.plane {
+        if let Ok(transform) = plane_grid.get_single() {
+            for radius in PLANE_CIRCLES {
+                label(transform.translation + Vec3::X * radius * AU_TO_UNIT_SCALE, format!("{} AU", radius), Color::rgb(0.6, 0.6, 0.6));
+            }
+        }
+    }
+}
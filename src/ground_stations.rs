@@ -0,0 +1,141 @@
+use bevy::{prelude::*, math::{DQuat, DVec3}};
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+
+use crate::{astro::{julian_date, sim_date}, bodies::{EARTH_RADIUS_KM, KM_TO_UNIT, SPEED_OF_LIGHT_KM_S}, body::{update_bodies, Velocity}, jwst::{orbit_around_l2, JWST}, rotation::Rotation, targets::runs, ui::SimTime, SimState};
+
+/// Hours covered by the pass timeline
+pub const TIMELINE_HOURS: usize = 48;
+/// Samples of the timeline per hour
+pub const SAMPLES_PER_HOUR: usize = 12;
+
+pub struct GroundStationPlugin;
+
+impl Plugin for GroundStationPlugin {
+
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<GroundStations>()
+        .register_inspectable::<GroundStations>()
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(update_ground_stations.after(orbit_around_l2).after(update_bodies)));
+    }
+
+}
+
+/// Antenna on the rotating Earth, the latitude and longitude are geocentric in degrees
+pub struct GroundStation {
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Current elevation of JWST above the horizon in degrees
+    pub elevation: f64,
+    /// Current distance to JWST in km
+    pub range: f64,
+    /// Whether JWST is above the elevation mask at each sample of the next [TIMELINE_HOURS]
+    pub timeline: Vec<bool>,
+}
+
+impl GroundStation {
+
+    pub fn new(name: &str, latitude: f64, longitude: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            latitude,
+            longitude,
+            elevation: 0.0,
+            range: 0.0,
+            timeline: Vec::new()
+        }
+    }
+
+    /// The three Deep Space Network complexes
+    pub fn dsn() -> Vec<Self> {
+        vec![
+            Self::new("Goldstone", 35.4267, -116.8900),
+            Self::new("Madrid", 40.4313, -4.2480),
+            Self::new("Canberra", -35.4014, 148.9817)
+        ]
+    }
+
+    /// One-way light time to JWST in seconds
+    pub fn light_time(&self) -> f64 {
+        self.range / SPEED_OF_LIGHT_KM_S
+    }
+
+    /// Position relative to Earth's center in the body-fixed frame in km, on a spherical Earth
+    pub fn position(&self) -> DVec3 {
+        let (latitude, longitude) = (self.latitude.to_radians(), self.longitude.to_radians());
+        DVec3::new(latitude.cos() * longitude.cos(), latitude.cos() * longitude.sin(), latitude.sin()) * EARTH_RADIUS_KM
+    }
+
+    /// Elevation in degrees and range in km of a target at `target` (km from Earth's center in the ecliptic frame)
+    /// while Earth has the orientation `earth`
+    pub fn look_at(&self, earth: DQuat, target: DVec3) -> (f64, f64) {
+        let station = earth * self.position();
+        let line_of_sight = target - station;
+        let range = line_of_sight.length();
+        let elevation = (line_of_sight.dot(station.normalize()) / range).clamp(-1.0, 1.0).asin().to_degrees();
+        (elevation, range)
+    }
+
+    /// First and last sample of each pass in the timeline
+    pub fn passes(&self) -> Vec<(usize, usize)> {
+        runs(&self.timeline)
+    }
+
+}
+
+/// Deep Space Network stations tracking JWST, the pass window is toggled in the UI
+#[derive(Resource, Inspectable)]
+pub struct GroundStations {
+    pub show: bool,
+    /// Lowest elevation in degrees a station can track at
+    pub elevation_mask: f64,
+    #[inspectable(ignore)]
+    pub stations: Vec<GroundStation>,
+}
+
+impl Default for GroundStations {
+
+    fn default() -> Self {
+        Self {
+            show: false,
+            elevation_mask: 10.0,
+            stations: GroundStation::dsn()
+        }
+    }
+
+}
+
+/// Looks at JWST from every station now and over the next [TIMELINE_HOURS]. Ahead of the current time JWST's
+/// position relative to Earth is extrapolated with its current relative velocity, which is slow enough at L2.
+fn update_ground_stations(
+    mut settings: ResMut<GroundStations>,
+    sim_time: Res<SimTime>,
+    earth: Query<(&Name, &Transform, &Velocity, &Rotation)>,
+    jwst: Query<(&Transform, &Velocity), With<JWST>>
+) {
+    if !settings.show {
+        return;
+    }
+    let (earth, (jwst, jwst_velocity)) = match (earth.iter().find(|(name, _, _, _)| name.as_str() == "Earth"), jwst.get_single()) {
+        (Some(earth), Ok(jwst)) => (earth, jwst),
+        _ => return
+    };
+    let (_, earth, earth_velocity, rotation) = earth;
+    let unit_to_km = 1.0 / KM_TO_UNIT as f64;
+    let position = (jwst.translation - earth.translation).as_dvec3() * unit_to_km;
+    // km per day
+    let velocity = (jwst_velocity.0 - earth_velocity.0).as_dvec3() * unit_to_km;
+    let now = julian_date(sim_date(sim_time.0));
+    let mask = settings.elevation_mask;
+    for station in settings.stations.iter_mut() {
+        let (elevation, range) = station.look_at(rotation.orientation(now), position);
+        station.elevation = elevation;
+        station.range = range;
+        let timeline = (0..TIMELINE_HOURS * SAMPLES_PER_HOUR).map(|sample| {
+            let days = sample as f64 / (24 * SAMPLES_PER_HOUR) as f64;
+            station.look_at(rotation.orientation(now + days), position + velocity * days).0 >= mask
+        }).collect();
+        station.timeline = timeline;
+    }
+}
@@ -0,0 +1,240 @@
+use bevy::{prelude::*, math::DVec3, render::{mesh::PrimitiveTopology, view::NoFrustumCulling}};
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+use bevy_mod_picking::Selection;
+
+use crate::{body::{Kinematic, Spacecraft, Velocity}, cr3bp, frame::{RotatingFrame, update_rotating_frame}, lines::line_mesh, SimState};
+
+const OVERLAY_COLOR: [f32; 3] = [0.9, 0.25, 0.2];
+const OVERLAY_ALPHA: f32 = 0.35;
+
+pub struct JacobiPlugin;
+
+impl Plugin for JacobiPlugin {
+
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<ZeroVelocityCurves>()
+        .register_inspectable::<ZeroVelocityCurves>()
+        .register_inspectable::<JacobiConstant>()
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(update_jacobi_constants.after(update_rotating_frame)))
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(update_zero_velocity_overlay.after(update_jacobi_constants)))
+        .add_system_set(SystemSet::on_exit(SimState::Simulation).with_system(despawn_overlay));
+    }
+
+}
+
+/// Jacobi constant of a spacecraft in the restricted three-body problem of the [RotatingFrame] primaries
+#[derive(Component, Inspectable, Default)]
+pub struct JacobiConstant(pub f64);
+
+/// Zero-velocity curves in the orbital plane of the primaries. Everything where 2Ω < C is forbidden
+/// for a body with Jacobi constant C.
+#[derive(Resource, Inspectable)]
+pub struct ZeroVelocityCurves {
+    pub enabled: bool,
+    /// Take C from the selected spacecraft (or the first one if none is selected). Kinematic spacecraft
+    /// follow a scripted path with no physical C and are skipped, the manual constant stays then.
+    pub follow_spacecraft: bool,
+    pub constant: f64,
+    /// Half width of the overlay around the secondary in units of the primaries' distance
+    pub extent: f64,
+    pub resolution: usize,
+}
+
+impl Default for ZeroVelocityCurves {
+
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            follow_spacecraft: true,
+            constant: 3.0009,
+            extent: 0.03,
+            resolution: 200
+        }
+    }
+
+}
+
+#[derive(Component)]
+struct ZeroVelocityOverlay {
+    fill: Handle<Mesh>,
+    curve: Handle<Mesh>,
+    built_for: Option<(f64, f64, f64, usize)>,
+}
+
+fn update_jacobi_constants(
+    frame: Res<RotatingFrame>,
+    mut spacecraft: Query<(&Transform, &Velocity, &mut JacobiConstant), With<Spacecraft>>
+) {
+    if frame.distance <= 0.0 || frame.angular_velocity <= 0.0 {
+        return;
+    }
+    for (transform, velocity, mut jacobi) in spacecraft.iter_mut() {
+        let (pos, vel) = frame.to_synodic(transform.translation, velocity.0);
+        jacobi.0 = cr3bp::jacobi_constant(frame.mu as f64, pos, vel);
+    }
+}
+
+fn update_zero_velocity_overlay(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut settings: ResMut<ZeroVelocityCurves>,
+    frame: Res<RotatingFrame>,
+    spacecraft: Query<(&JacobiConstant, &Selection), Without<Kinematic>>,
+    mut overlay: Query<(&mut ZeroVelocityOverlay, &mut Transform, &mut Visibility)>
+) {
+    if settings.follow_spacecraft {
+        let selected = spacecraft.iter().find(|(_, selection)| selection.selected());
+        if let Some((jacobi, _)) = selected.or_else(|| spacecraft.iter().next()) {
+            settings.constant = jacobi.0;
+        }
+    }
+    let (mut overlay, mut transform, mut visibility) = match overlay.get_single_mut() {
+        Ok(overlay) => overlay,
+        Err(_) => {
+            if settings.enabled {
+                spawn_overlay(&mut commands, &mut meshes, &mut materials);
+            }
+            return;
+        }
+    };
+    visibility.is_visible = settings.enabled && frame.distance > 0.0;
+    if !visibility.is_visible {
+        return;
+    }
+    *transform = frame.synodic_transform();
+
+    let mu = frame.mu as f64;
+    let outdated = match overlay.built_for {
+        Some((c, m, extent, resolution)) => {
+            (c - settings.constant).abs() > 1e-7 || (m - mu).abs() > 1e-9 || extent != settings.extent || resolution != settings.resolution
+        },
+        None => true
+    };
+    if outdated {
+        let (fill, curve) = zero_velocity_meshes(mu, settings.constant, settings.extent, settings.resolution.max(2));
+        if let Some(mesh) = meshes.get_mut(&overlay.fill) {
+            *mesh = fill;
+        }
+        if let Some(mesh) = meshes.get_mut(&overlay.curve) {
+            *mesh = curve;
+        }
+        overlay.built_for = Some((settings.constant, mu, settings.extent, settings.resolution));
+    }
+}
+
+fn spawn_overlay(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>
+) {
+    let fill = meshes.add(line_mesh(PrimitiveTopology::TriangleList, vec![Vec3::ZERO; 3]));
+    let curve = meshes.add(line_mesh(PrimitiveTopology::LineList, vec![Vec3::ZERO; 2]));
+    commands.spawn(SpatialBundle::default())
+    .insert(Name::new("Zero-velocity curves"))
+    .insert(ZeroVelocityOverlay {
+        fill: fill.clone(),
+        curve: curve.clone(),
+        built_for: None
+    })
+    .with_children(|commands| {
+        commands.spawn(MaterialMeshBundle {
+            mesh: fill,
+            material: materials.add(StandardMaterial {
+                base_color: Color::WHITE,
+                unlit: true,
+                alpha_mode: AlphaMode::Blend,
+                double_sided: true,
+                cull_mode: None,
+                ..default()
+            }),
+            ..default()
+        })
+        .insert(NoFrustumCulling);
+        commands.spawn(MaterialMeshBundle {
+            mesh: curve,
+            material: materials.add(StandardMaterial {
+                base_color: Color::rgb(OVERLAY_COLOR[0], OVERLAY_COLOR[1], OVERLAY_COLOR[2]),
+                unlit: true,
+                ..default()
+            }),
+            ..default()
+        })
+        .insert(NoFrustumCulling);
+    });
+}
+
+/// Builds the forbidden region (translucent fill) and its boundary (lines) on a grid around the secondary.
+/// Positions are in synodic coordinates relative to the barycenter.
+fn zero_velocity_meshes(mu: f64, constant: f64, extent: f64, resolution: usize) -> (Mesh, Mesh) {
+    let center = DVec3::new(1.0 - mu, 0.0, 0.0);
+    let step = 2.0 * extent / resolution as f64;
+    let point = |i: usize, j: usize| center + DVec3::new(i as f64 * step - extent, j as f64 * step - extent, 0.0);
+    // 2Ω - C is negative inside the forbidden region, it is clamped to stay finite near the primaries
+    let value = |p: DVec3| (2.0 * cr3bp::effective_potential(mu, p) - constant).min(1e6);
+
+    let stride = resolution + 1;
+    let mut positions: Vec<[f32; 3]> = Vec::with_capacity(stride * stride);
+    let mut colors: Vec<[f32; 4]> = Vec::with_capacity(stride * stride);
+    let mut values: Vec<f64> = Vec::with_capacity(stride * stride);
+    for j in 0..stride {
+        for i in 0..stride {
+            let p = point(i, j);
+            let f = value(p);
+            let alpha = if f < 0.0 { OVERLAY_ALPHA } else { 0.0 };
+            positions.push(p.as_vec3().to_array());
+            colors.push([OVERLAY_COLOR[0], OVERLAY_COLOR[1], OVERLAY_COLOR[2], alpha]);
+            values.push(f);
+        }
+    }
+
+    let mut indices: Vec<u32> = Vec::new();
+    let mut segments: Vec<Vec3> = Vec::new();
+    for j in 0..resolution {
+        for i in 0..resolution {
+            // corners counter-clockwise starting bottom left
+            let corners = [j * stride + i, j * stride + i + 1, (j + 1) * stride + i + 1, (j + 1) * stride + i];
+            if corners.iter().any(|c| values[*c] < 0.0) {
+                indices.extend_from_slice(&[corners[0] as u32, corners[1] as u32, corners[2] as u32]);
+                indices.extend_from_slice(&[corners[0] as u32, corners[2] as u32, corners[3] as u32]);
+            }
+            // marching squares: interpolate the sign changes along the cell edges
+            let mut crossings: Vec<Vec3> = Vec::new();
+            for edge in 0..4 {
+                let (a, b) = (corners[edge], corners[(edge + 1) % 4]);
+                if (values[a] < 0.0) != (values[b] < 0.0) {
+                    let t = (values[a] / (values[a] - values[b])) as f32;
+                    let (pa, pb) = (Vec3::from(positions[a]), Vec3::from(positions[b]));
+                    crossings.push(pa + (pb - pa) * t);
+                }
+            }
+            for pair in crossings.chunks_exact(2) {
+                segments.push(pair[0]);
+                segments.push(pair[1]);
+            }
+        }
+    }
+    // keep the buffers non-empty when nothing is forbidden
+    if indices.is_empty() {
+        indices = vec![0; 3];
+    }
+    if segments.is_empty() {
+        segments = vec![Vec3::ZERO; 2];
+    }
+
+    let mut fill = Mesh::new(PrimitiveTopology::TriangleList);
+    fill.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    fill.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    fill.set_indices(Some(bevy::render::mesh::Indices::U32(indices)));
+    (fill, line_mesh(PrimitiveTopology::LineList, segments))
+}
+
+fn despawn_overlay(
+    mut commands: Commands,
+    overlay: Query<Entity, With<ZeroVelocityOverlay>>
+) {
+    for entity in overlay.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,7 +1,8 @@
 use bevy::{prelude::*, render::view::NoFrustumCulling};
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
 use bevy_mod_picking::PickableBundle;
 
-use crate::{menu::setup, lagrange::{LagrangePoint, calculate_lagrange_points}, bodies::Body, body::{body_focus, Sun, Pause}, SimState, speed::Speed};
+use crate::{attitude::Attitude, menu::setup, lagrange::{LagrangePoint, calculate_lagrange_points}, bodies::{Body, KM_TO_UNIT}, body::{body_focus, Sun, Pause, Spacecraft, Velocity, Radius, Kinematic}, radiation::SolarRadiation, SimState, speed::Speed, jacobi::JacobiConstant};
 
 pub struct JWSTPlugin;
 
@@ -9,7 +10,10 @@ impl Plugin for JWSTPlugin {
     
     fn build(&self, app: &mut App) {
         app
+        .init_resource::<ScriptedOrbit>()
+        .register_inspectable::<ScriptedOrbit>()
         .add_system_set(SystemSet::on_enter(SimState::Simulation).with_system(setup_jwst.after(setup)))
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(apply_scripted_orbit.before(orbit_around_l2)))
         .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(orbit_around_l2.after(calculate_lagrange_points)));
     }
     
@@ -18,8 +22,22 @@ impl Plugin for JWSTPlugin {
 #[derive(Component)]
 pub struct JWST;
 
+/// Keeps JWST on the scripted circle around L2, when disabled it flies freely under gravity and radiation pressure.
+/// Enabling solar radiation pressure turns it off, otherwise the pressure would never act on JWST.
+#[derive(Resource, Inspectable)]
+pub struct ScriptedOrbit(pub bool);
+
+impl Default for ScriptedOrbit {
+    
+    fn default() -> Self {
+        Self(true)
+    }
+    
+}
+
 fn setup_jwst(mut commands: Commands, assets: Res<AssetServer>, mut meshes: ResMut<Assets<Mesh>>) {
-    commands
+    let jwst = Body::jwst();
+    let entity = commands
         .spawn(SpatialBundle::from_transform(Transform::from_xyz(
             4.0, 7.0, 0.0,
         )))
@@ -27,7 +45,12 @@ fn setup_jwst(mut commands: Commands, assets: Res<AssetServer>, mut meshes: ResM
         .insert(meshes.add(shape::UVSphere { radius: 0.002, ..default() }.into()))
         .insert(PickableBundle::default())
         .insert(JWST)
-        .insert(Body::jwst().body)
+        .insert(Spacecraft)
+        .insert(JacobiConstant::default())
+        .insert(Attitude::default())
+        .insert(Radius(jwst.physical_radius * KM_TO_UNIT))
+        .insert(Kinematic)
+        .insert(jwst.body)
         .with_children(|commands| {
             commands.spawn((
                 SceneBundle {
@@ -36,11 +59,50 @@ fn setup_jwst(mut commands: Commands, assets: Res<AssetServer>, mut meshes: ResM
                     ..Default::default()
                 },
             ));
-        });
+        })
+        .id();
+    if let Some(radiation) = jwst.radiation {
+        commands.entity(entity).insert(radiation);
+    }
+}
+
+/// Position and velocity at rest at `point` in the frame rotating with Earth around the Sun, from the positions
+/// and velocities of the Sun and Earth
+pub fn rest_at(point: &LagrangePoint, sun: (Vec3, Vec3), earth: (Vec3, Vec3)) -> (Vec3, Vec3) {
+    let position = point.position(sun.0, earth.0);
+    let (r, v) = (earth.0 - sun.0, earth.1 - sun.1);
+    let angular_velocity = r.cross(v) / r.length_squared();
+    (position, sun.1 + angular_velocity.cross(position - sun.0))
+}
+
+fn apply_scripted_orbit(
+    mut commands: Commands,
+    mut scripted: ResMut<ScriptedOrbit>,
+    radiation: Res<SolarRadiation>,
+    mut jwst: Query<(Entity, Option<&Kinematic>, &mut Transform, &mut Velocity), With<JWST>>,
+    bodies: Query<(&Name, &Transform, &Velocity), Without<JWST>>,
+    lagrange_points: Query<(&LagrangePoint, &Name)>
+) {
+    if radiation.is_changed() && radiation.enabled && scripted.0 {
+        scripted.0 = false;
+    }
+    for (entity, kinematic, mut transform, mut velocity) in jwst.iter_mut() {
+        if scripted.0 && kinematic.is_none() {
+            commands.entity(entity).insert(Kinematic);
+        } else if !scripted.0 && kinematic.is_some() {
+            commands.entity(entity).remove::<Kinematic>();
+            // the scripted circle is no physical orbit, start from rest at L2 in the frame rotating with Earth
+            let find = |name: &str| bodies.iter().find(|(body, _, _)| body.as_str() == name).map(|(_, transform, velocity)| (transform.translation, velocity.0));
+            let l2 = lagrange_points.iter().find(|(_, name)| name.as_str() == "SE-L2").map(|(point, _)| point);
+            if let (Some(sun), Some(earth), Some(l2)) = (find("Sun"), find("Earth"), l2) {
+                (transform.translation, velocity.0) = rest_at(l2, sun, earth);
+            }
+        }
+    }
 }
 
 pub fn orbit_around_l2(
-    mut jwst: Query<(&mut Transform, &JWST, Without<LagrangePoint>)>,
+    mut jwst: Query<(&mut Transform, &mut Velocity, &JWST, With<Kinematic>, Without<LagrangePoint>)>,
     sun: Query<(&Transform, &Sun, Without<LagrangePoint>, Without<JWST>)>,
     lagrange_points: Query<(&LagrangePoint, &Name, &Transform)>,
     time: Res<Time>,
@@ -51,15 +113,21 @@ pub fn orbit_around_l2(
         if name.as_str() == "SE-L2" && !pause.0 {
                 if let Ok(result) = jwst.get_single_mut() {
                     if let Ok(sun) = sun.get_single() {
-                        let mut jwst = result.0;
+                        let (mut jwst, mut velocity, _, _, _) = result;
                         let sun_location = sun.0.translation;
                         let angle = time.elapsed_seconds() * speed.0;
+                        let old_position = jwst.translation;
                         // update the position of the entity based on the orbit radius and angle
                         jwst.translation = center.translation + Vec3::new(
                             0.02 * angle.cos(),
                             0.02 * angle.sin(),
                             0.0,
                         );   
+                        // the scripted orbit has no velocity of its own, derive it from the movement
+                        let dt = time.delta_seconds() * speed.0;
+                        if dt > 0.0 {
+                            velocity.0 = (jwst.translation - old_position) / dt;
+                        }
                     }
             }
         }
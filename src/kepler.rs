@@ -0,0 +1,154 @@
+use bevy::math::{DQuat, DVec3};
+
+/// Gaussian gravitational constant k in rad/day, GM of the Sun is k² in AU³/day²
+pub const GAUSSIAN_GRAVITATIONAL_CONSTANT: f64 = 0.017_202_098_95;
+
+/// Heliocentric osculating elements in the J2000 ecliptic frame, given by the perihelion so that
+/// elliptic, parabolic and hyperbolic orbits share one representation
+#[derive(Clone, Copy, Debug)]
+pub struct OrbitalElements {
+    /// Perihelion distance in AU
+    pub perihelion_distance: f64,
+    pub eccentricity: f64,
+    /// Inclination in degrees
+    pub inclination: f64,
+    /// Longitude of the ascending node in degrees
+    pub node: f64,
+    /// Argument of perihelion in degrees
+    pub perihelion: f64,
+    /// Time of perihelion passage as julian date
+    pub perihelion_time: f64,
+}
+
+impl OrbitalElements {
+
+    /// Elements of an elliptic orbit given by the semi-major axis (AU) and the mean anomaly (degrees) at `epoch` (julian date)
+    pub fn from_mean_anomaly(semi_major_axis: f64, eccentricity: f64, inclination: f64, node: f64, perihelion: f64, mean_anomaly: f64, epoch: f64) -> Self {
+        let mu = GAUSSIAN_GRAVITATIONAL_CONSTANT * GAUSSIAN_GRAVITATIONAL_CONSTANT;
+        Self::around(mu, semi_major_axis, eccentricity, inclination, node, perihelion, mean_anomaly, epoch)
+    }
+
+    /// Same as [OrbitalElements::from_mean_anomaly] for an orbit around a body with the gravitational parameter `mu`,
+    /// lengths can be in any unit matching `mu` (per day²)
+    #[allow(clippy::too_many_arguments)]
+    pub fn around(mu: f64, semi_major_axis: f64, eccentricity: f64, inclination: f64, node: f64, perihelion: f64, mean_anomaly: f64, epoch: f64) -> Self {
+        let mean_motion = (mu / semi_major_axis.powi(3)).sqrt();
+        // the closest perihelion passage, so the anomaly at the epoch stays small
+        let mut anomaly = mean_anomaly.to_radians() % std::f64::consts::TAU;
+        if anomaly > std::f64::consts::PI {
+            anomaly -= std::f64::consts::TAU;
+        }
+        Self {
+            perihelion_distance: semi_major_axis * (1.0 - eccentricity),
+            eccentricity,
+            inclination,
+            node,
+            perihelion,
+            perihelion_time: epoch - anomaly / mean_motion
+        }
+    }
+
+    /// Osculating elements of a `position` and `velocity` (per day) relative to a body with the gravitational
+    /// parameter `mu` at the julian date `time`, the inverse of [OrbitalElements::state_around]
+    pub fn from_state(mu: f64, position: DVec3, velocity: DVec3, time: f64) -> Self {
+        let h = position.cross(velocity);
+        let normal = h.normalize();
+        let e_vector = (position * (velocity.length_squared() - mu / position.length()) - velocity * position.dot(velocity)) / mu;
+        let e = e_vector.length();
+        let q = h.length_squared() / mu / (1.0 + e);
+        let inclination = normal.z.clamp(-1.0, 1.0).acos();
+        // in the ecliptic the node is undefined, measure from the x axis instead
+        let node = if inclination.sin().abs() < 1e-12 { 0.0 } else { h.x.atan2(-h.y) };
+        let ascending = DVec3::new(node.cos(), node.sin(), 0.0);
+        // on a circular orbit the perihelion is undefined, put it at the node
+        let periapsis = if e < 1e-12 { ascending } else { e_vector / e };
+        let angle = |from: DVec3, to: DVec3| from.cross(to).dot(normal).atan2(from.dot(to));
+        let perihelion = angle(ascending, periapsis);
+        let anomaly = angle(periapsis, position);
+        let since_perihelion = if (e - 1.0).abs() < 1e-8 {
+            let s = (anomaly / 2.0).tan();
+            (s.powi(3) + 3.0 * s) / (3.0 * (mu / (2.0 * q.powi(3))).sqrt())
+        } else if e < 1.0 {
+            let a = q / (1.0 - e);
+            let eccentric_anomaly = 2.0 * ((1.0 - e).sqrt() * (anomaly / 2.0).sin()).atan2((1.0 + e).sqrt() * (anomaly / 2.0).cos());
+            (eccentric_anomaly - e * eccentric_anomaly.sin()) / (mu / a.powi(3)).sqrt()
+        } else {
+            let a = q / (e - 1.0);
+            let hyperbolic_anomaly = 2.0 * (((e - 1.0) / (e + 1.0)).sqrt() * (anomaly / 2.0).tan()).atanh();
+            (e * hyperbolic_anomaly.sinh() - hyperbolic_anomaly) / (mu / a.powi(3)).sqrt()
+        };
+        Self {
+            perihelion_distance: q,
+            eccentricity: e,
+            inclination: inclination.to_degrees(),
+            node: node.to_degrees(),
+            perihelion: perihelion.to_degrees(),
+            perihelion_time: time - since_perihelion
+        }
+    }
+
+    /// Heliocentric position (AU) and velocity (AU/day) at the julian date `time`
+    pub fn state(&self, time: f64) -> (DVec3, DVec3) {
+        self.state_around(GAUSSIAN_GRAVITATIONAL_CONSTANT * GAUSSIAN_GRAVITATIONAL_CONSTANT, time)
+    }
+
+    /// Position and velocity (per day) relative to a body with the gravitational parameter `mu` at the julian date `time`
+    pub fn state_around(&self, mu: f64, time: f64) -> (DVec3, DVec3) {
+        let (q, e) = (self.perihelion_distance, self.eccentricity);
+        let t = time - self.perihelion_time;
+        // true anomaly from the conic's version of Kepler's equation
+        let anomaly = if (e - 1.0).abs() < 1e-8 {
+            // Barker's equation s³ + 3s = W with s = tan(ν/2)
+            let w = 3.0 * (mu / (2.0 * q.powi(3))).sqrt() * t;
+            let y = (w / 2.0 + (w * w / 4.0 + 1.0).sqrt()).cbrt();
+            2.0 * (y - 1.0 / y).atan()
+        } else if e < 1.0 {
+            let a = q / (1.0 - e);
+            let mean_anomaly = (mu / a.powi(3)).sqrt() * t;
+            let eccentric_anomaly = solve_kepler(mean_anomaly, e);
+            2.0 * ((1.0 + e).sqrt() * (eccentric_anomaly / 2.0).sin()).atan2((1.0 - e).sqrt() * (eccentric_anomaly / 2.0).cos())
+        } else {
+            let a = q / (e - 1.0);
+            let mean_anomaly = (mu / a.powi(3)).sqrt() * t;
+            let hyperbolic_anomaly = solve_hyperbolic_kepler(mean_anomaly, e);
+            2.0 * (((e + 1.0) / (e - 1.0)).sqrt() * (hyperbolic_anomaly / 2.0).tanh()).atan()
+        };
+        let p = q * (1.0 + e);
+        let r = p / (1.0 + e * anomaly.cos());
+        let position = DVec3::new(r * anomaly.cos(), r * anomaly.sin(), 0.0);
+        let velocity = DVec3::new(-anomaly.sin(), e + anomaly.cos(), 0.0) * (mu / p).sqrt();
+        // perifocal to ecliptic: Rz(Ω) Rx(i) Rz(ω)
+        let rotation = DQuat::from_rotation_z(self.node.to_radians())
+            * DQuat::from_rotation_x(self.inclination.to_radians())
+            * DQuat::from_rotation_z(self.perihelion.to_radians());
+        (rotation * position, rotation * velocity)
+    }
+
+}
+
+/// Solves M = E - e sin E for the eccentric anomaly
+fn solve_kepler(mean_anomaly: f64, e: f64) -> f64 {
+    let mean_anomaly = (mean_anomaly + std::f64::consts::PI).rem_euclid(std::f64::consts::TAU) - std::f64::consts::PI;
+    let mut eccentric_anomaly = if e > 0.8 { std::f64::consts::PI.copysign(mean_anomaly) } else { mean_anomaly };
+    for _ in 0..50 {
+        let step = (eccentric_anomaly - e * eccentric_anomaly.sin() - mean_anomaly) / (1.0 - e * eccentric_anomaly.cos());
+        eccentric_anomaly -= step;
+        if step.abs() < 1e-14 {
+            break;
+        }
+    }
+    eccentric_anomaly
+}
+
+/// Solves M = e sinh H - H for the hyperbolic anomaly
+fn solve_hyperbolic_kepler(mean_anomaly: f64, e: f64) -> f64 {
+    let mut hyperbolic_anomaly = (2.0 * mean_anomaly.abs() / e + 1.8).ln().copysign(mean_anomaly);
+    for _ in 0..100 {
+        let step = (e * hyperbolic_anomaly.sinh() - hyperbolic_anomaly - mean_anomaly) / (e * hyperbolic_anomaly.cosh() - 1.0);
+        hyperbolic_anomaly -= step;
+        if step.abs() < 1e-14 {
+            break;
+        }
+    }
+    hyperbolic_anomaly
+}
@@ -10,6 +10,15 @@ pub struct LagrangePlugin;
 #[derive(Component, Inspectable)]
 pub struct LagrangePoint(pub f32); //distance from earth 10 unit = 1 AU
 
+impl LagrangePoint {
+
+    /// Position of the point on the line through the Sun and Earth
+    pub fn position(&self, sun: Vec3, earth: Vec3) -> Vec3 {
+        (sun - earth).normalize_or_zero() * self.0 + earth
+    }
+
+}
+
 impl Plugin for LagrangePlugin {
     
     fn build(&self, app: &mut bevy::prelude::App) {
@@ -73,9 +82,7 @@ pub fn calculate_lagrange_points(
     }
     if let (Some(spos), Some(epos)) = (sun, earth) {
         for (point, mut transform) in set.p0().iter_mut() {
-            let normalized_pos = (spos - epos).normalize_or_zero() * point.0;
-            let actual_pos = normalized_pos + epos;
-            transform.translation = actual_pos;
+            transform.translation = point.position(spos, epos);
             transform.rotation = camera_rot;
         }
     } 
@@ -1,60 +1,120 @@
-use bevy::{prelude::{Component, Plugin, Commands, ResMut, Assets, Query, Transform, Handle, IntoSystemDescriptor, Vec3}, time::{Timer, TimerMode}, math::Vec3A};
-use bevy_polyline::{prelude::Polyline, PolylinePlugin};
-use ringbuffer::{ConstGenericRingBuffer, RingBufferExt, RingBufferWrite};
+use std::collections::VecDeque;
 
-use crate::body::{EnableLines, update_bodies};
+use bevy::{prelude::*, render::{mesh::PrimitiveTopology, view::NoFrustumCulling}};
+
+use crate::{body::{EnableLines, Pause, update_bodies}, frame::{RotatingFrame, update_rotating_frame}, minor_bodies::MinorBody, SimState};
 
 pub const TRAIL_LENGTH: usize = 1024 * 5;
-const MINIMUM_ANGLE: f32 = 1.48341872;
+const MINIMUM_DISTANCE: f32 = 0.0005;
+
+#[derive(Resource, Default)]
+pub struct ShowTrails(pub bool);
 
-#[derive(Component, Clone, Default, Debug)]
-pub struct Trail(pub ConstGenericRingBuffer<Vec3, TRAIL_LENGTH>);
+/// Line strip following `owner`. The points are stored in the coordinates of the [RotatingFrame]
+/// so that trails keep their shape when displayed in the co-rotating frame.
+#[derive(Component)]
+pub struct Trail {
+    pub owner: Entity,
+    pub points: VecDeque<Vec3>,
+}
 
 pub struct LinePlugin;
 
 impl Plugin for LinePlugin {
-    
-    fn build(&self, app: &mut bevy::prelude::App) {
+
+    fn build(&self, app: &mut App) {
         app
-        .add_plugin(PolylinePlugin)
-        .add_system(update_trails.after(update_bodies));
+        .init_resource::<ShowTrails>()
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(spawn_trails))
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(update_trails.after(update_bodies).after(update_rotating_frame)))
+        .add_system_set(SystemSet::on_exit(SimState::Simulation).with_system(despawn_trails));
+    }
+
+}
+
+/// Creates a mesh with the given topology, used for trails and other overlays made of lines
+pub fn line_mesh(topology: PrimitiveTopology, points: Vec<Vec3>) -> Mesh {
+    let mut mesh = Mesh::new(topology);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, points.iter().map(|p| p.to_array()).collect::<Vec<[f32; 3]>>());
+    mesh
+}
+
+/// Gives every new body a trail, except minor bodies which come by the thousand
+fn spawn_trails(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    bodies: Query<(Entity, &Name, &Transform), (Added<EnableLines>, Without<MinorBody>)>
+) {
+    for (entity, name, transform) in bodies.iter() {
+        let hue = name.bytes().fold(0u32, |hash, b| hash.wrapping_mul(31).wrapping_add(b as u32)) % 360;
+        commands.spawn(MaterialMeshBundle {
+            mesh: meshes.add(line_mesh(PrimitiveTopology::LineStrip, vec![transform.translation; 2])),
+            material: materials.add(StandardMaterial {
+                base_color: Color::hsl(hue as f32, 0.7, 0.6),
+                unlit: true,
+                ..default()
+            }),
+            visibility: Visibility::INVISIBLE,
+            ..default()
+        })
+        .insert(NoFrustumCulling)
+        .insert(Trail {
+            owner: entity,
+            points: VecDeque::new()
+        });
     }
-    
 }
 
 fn update_trails(
-    mut polylines: ResMut<Assets<Polyline>>,
-    mut query: Query<(&EnableLines, &Transform, &mut Trail, &Handle<Polyline>)>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut trails: Query<(Entity, &mut Trail, &Handle<Mesh>, &mut Visibility)>,
+    bodies: Query<(&Transform, &EnableLines, &Visibility), Without<Trail>>,
+    frame: Res<RotatingFrame>,
+    show_trails: Res<ShowTrails>,
+    pause: Res<Pause>,
+    mut last_frame: Local<(bool, String, String)>
 ) {
-     query.for_each_mut(|(enable_lines, transform, mut trail, polyline)| {
-        if let Some(position) = trail.0.back() {
-            let last_vec = *position - transform.translation;
-            let last_last_vec = if let Some(position) = trail.0.get(-2) {
-                *position - transform.translation
-            } else {
-                last_vec
-            };
-            let gt_min_angle = last_vec.dot(last_last_vec) > MINIMUM_ANGLE;
-            if gt_min_angle {
-                trail.0.push(transform.translation);
-                polylines.get_mut(polyline).unwrap().vertices =
-                    trail.0.iter().map(|v| Vec3::from(*v)).collect()
-            } else {
-                // If the last point didn't actually add much of a curve, just overwrite it.
-                if polylines.get_mut(polyline).unwrap().vertices.len() > 1 {
-                    *trail.0.get_mut(-1).unwrap() = transform.translation;
-                    *polylines
-                        .get_mut(polyline)
-                        .unwrap()
-                        .vertices
-                        .last_mut()
-                        .unwrap() = transform.translation.into();
-                }
+    let frame_key = (frame.enabled, frame.primary.clone(), frame.secondary.clone());
+    let frame_changed = *last_frame != frame_key;
+    *last_frame = frame_key;
+    for (entity, mut trail, mesh, mut visibility) in trails.iter_mut() {
+        let (transform, enable_lines, body_visibility) = match bodies.get(trail.owner) {
+            Ok(body) => body,
+            Err(_) => {
+                commands.entity(entity).despawn_recursive();
+                continue;
             }
-        } else {
-            trail.0.push(transform.translation);
-            polylines.get_mut(polyline).unwrap().vertices =
-                trail.0.iter().map(|v| Vec3::from(*v)).collect()
+        };
+        if frame_changed {
+            trail.points.clear();
         }
-    });
-}
\ No newline at end of file
+        let position = frame.to_local(transform.translation);
+        let moved = trail.points.back().map_or(true, |last| last.distance(position) > MINIMUM_DISTANCE);
+        if moved && !pause.0 {
+            if trail.points.len() >= TRAIL_LENGTH {
+                trail.points.pop_front();
+            }
+            trail.points.push_back(position);
+        }
+        visibility.is_visible = show_trails.0 && enable_lines.0 && body_visibility.is_visible;
+        if visibility.is_visible {
+            if let Some(mesh) = meshes.get_mut(mesh) {
+                let mut points: Vec<Vec3> = trail.points.iter().map(|p| frame.to_world(*p)).collect();
+                // the last segment always ends at the body itself
+                points.push(transform.translation);
+                mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, points.iter().map(|p| p.to_array()).collect::<Vec<[f32; 3]>>());
+            }
+        }
+    }
+}
+
+fn despawn_trails(
+    mut commands: Commands,
+    trails: Query<Entity, With<Trail>>
+) {
+    for entity in trails.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
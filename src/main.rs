@@ -10,12 +10,39 @@ mod fps;
 mod menu;
 mod jwst;
 mod reset;
-
+mod frame;
+mod lines;
+mod cr3bp;
+mod jacobi;
+mod manifold;
+mod collision;
+mod relativity;
+mod astro;
+mod oblateness;
+mod eclipse;
+mod eclipse_search;
+mod ground_stations;
+mod radiation;
+mod drag;
+mod satellites;
+mod tle;
+mod kepler;
+mod measurement;
+mod minor_bodies;
+mod rotation;
+mod rings;
+mod stars;
+mod constellations;
+mod grids;
+mod targets;
+mod telescope;
+mod attitude;
 
 use std::time::Duration;
 
-use crate::bodies::Body;
-use crate::body::{BodyBundle, Sun};
+use crate::bodies::{Body, KM_TO_UNIT};
+use crate::astro::sim_epoch;
+use crate::body::{BodyBundle, Sun, Radius, Kinematic, Spacecraft};
 use crate::camera::*;
 use bevy::app::{ScheduleRunnerSettings, RunMode};
 use bevy::core_pipeline::{clear_color::ClearColorConfig, bloom::BloomSettings};
@@ -28,12 +55,35 @@ use bevy_egui::EguiPlugin;
 use bevy_inspector_egui::WorldInspectorPlugin;
 use bevy_mod_picking::{DefaultPickingPlugins, PickableBundle, PickingCameraBundle};
 use body::{Gravity, BodyPlugin};
+use collision::CollisionPlugin;
+use eclipse::EclipsePlugin;
+use eclipse_search::EclipseSearchPlugin;
+use ground_stations::GroundStationPlugin;
 use fps::FpsPlugin;
+use frame::FramePlugin;
+use jacobi::JacobiPlugin;
 use jwst::JWSTPlugin;
 use lagrange::LagrangePlugin;
+use lines::LinePlugin;
+use manifold::ManifoldPlugin;
+use oblateness::OblatenessPlugin;
+use radiation::RadiationPlugin;
+use drag::{DragPlugin, Decay};
+use satellites::{SatellitePlugin, LocalOrbit, Satellites};
+use minor_bodies::MinorBodyPlugin;
+use tle::{load_tles, tle_drag, tle_name, Sgp4Orbit, TLE_FILE};
+use relativity::{RelativityPlugin, Perihelion};
+use rotation::{RotationPlugin, Rotation};
+use rings::{RingPlugin, RingMaterial, spawn_rings};
 use menu::MenuPlugin;
 use reset::ResetPlugin;
 use skybox::SkyboxPlugin;
+use stars::StarPlugin;
+use constellations::ConstellationPlugin;
+use grids::GridPlugin;
+use targets::TargetPlugin;
+use telescope::TelescopePlugin;
+use attitude::AttitudePlugin;
 use speed::SpeedPlugin;
 use ui::UIPlugin;
 
@@ -65,29 +115,79 @@ fn main() {
         .add_plugin(UIPlugin)
         .add_plugin(LagrangePlugin)
         .add_plugin(SkyboxPlugin)
+        .add_plugin(StarPlugin)
+        .add_plugin(ConstellationPlugin)
+        .add_plugin(GridPlugin)
+        .add_plugin(TargetPlugin)
+        .add_plugin(TelescopePlugin)
+        .add_plugin(AttitudePlugin)
         .add_plugin(SpeedPlugin)
         .add_plugin(FpsPlugin)
         .add_plugin(JWSTPlugin)
         .add_plugin(MenuPlugin)
         .add_plugin(ResetPlugin)
+        .add_plugin(FramePlugin)
+        .add_plugin(JacobiPlugin)
+        .add_plugin(ManifoldPlugin)
+        .add_plugin(CollisionPlugin)
+        .add_plugin(EclipsePlugin)
+        .add_plugin(EclipseSearchPlugin)
+        .add_plugin(GroundStationPlugin)
+        .add_plugin(RelativityPlugin)
+        .add_plugin(OblatenessPlugin)
+        .add_plugin(RadiationPlugin)
+        .add_plugin(DragPlugin)
+        .add_plugin(SatellitePlugin)
+        .add_plugin(MinorBodyPlugin)
+        .add_plugin(RotationPlugin)
+        .add_plugin(RingPlugin)
       //  .add_plugin(LogDiagnosticsPlugin::default())
         .add_plugin(FrameTimeDiagnosticsPlugin::default())
         .add_state(SimState::Menu)
-      //  .add_plugin(LinePlugin)
+        .add_plugin(LinePlugin)
         .add_system_set(SystemSet::on_enter(SimState::Simulation).with_system(sim_setup))
         .run();
 }
 
+/// Body spawned by [sim_setup], to find the parents of moons and satellites
+struct Spawned {
+    name: String,
+    entity: Entity,
+    /// Radius of the mesh
+    radius: f32,
+    /// Ratio of the mesh radius to the physical radius
+    display_scale: f64,
+    position: Vec3,
+    velocity: Vec3,
+    mass: f32,
+}
+
+/// Whether a body at `position` is inside the Hill sphere of one of the bodies spawned so far,
+/// the Sun sits at the origin
+fn inside_hill_sphere(position: Vec3, spawned: &[Spawned], sun_mass: f32) -> bool {
+    spawned.iter().any(|other| {
+        let hill_radius = other.position.length() * (other.mass / (3.0 * sun_mass)).cbrt();
+        position.distance(other.position) < hill_radius
+    })
+}
+
 //mass scaled in 10^24 kg m
 fn sim_setup(
     mut commands: Commands,
     assets: Res<AssetServer>,
     mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut ring_materials: ResMut<Assets<RingMaterial>>,
     mut g: ResMut<Gravity>,
+    satellite_settings: Res<Satellites>,
 ) {
-    let bodies = vec![Body::earth(), Body::moon()/*, Body::saturn(), Body::venus(), Body::pluto(), Body::mercury(), Body::jupiter(), Body::mars(), Body::uranus()*/];
+    let mut bodies = vec![Body::earth(), Body::moon()/*, Body::saturn(), Body::titan(), Body::venus(), Body::pluto(), Body::mercury(), Body::jupiter(), Body::io(), Body::europa(), Body::ganymede(), Body::callisto(), Body::mars(), Body::phobos(), Body::deimos(), Body::uranus(), Body::neptune(), Body::triton()*/];
+    if satellite_settings.enabled {
+        bodies.extend([Body::iss(), Body::hubble()]);
+    }
 
     let sun_body = BodyBundle::new(1_988_500.0, Vec3::ZERO, Vec3::ZERO);
+    let sun_mass = sun_body.mass();
     commands
         .spawn(SpatialBundle::from_transform(Transform::from_xyz(
             0.0, 0.0, 0.0,
@@ -97,6 +197,7 @@ fn sim_setup(
         .insert(NotShadowCaster)
         .insert(PickableBundle::default())
         .insert(sun_body)
+        .insert(Radius(696_340.0 * KM_TO_UNIT))
         .insert(PointLightBundle {
             point_light: PointLight {
                 intensity: 10000.0,
@@ -109,6 +210,7 @@ fn sim_setup(
         })
         .insert(NoFrustumCulling)
         .insert(Sun)
+        .insert(Rotation::new(286.13, 0.0, 63.87, 0.0, 84.176, 14.184_4))
         .with_children(|commands| {
             commands.spawn(SceneBundle {
                 scene: assets.load("models/sun.glb#Scene0"),
@@ -117,15 +219,48 @@ fn sim_setup(
             });
         });
 
+    let mut spawned: Vec<Spawned> = Vec::new();
+    let mut satellites = Vec::new();
+    let mut element_sets = if satellite_settings.enabled { load_tles(TLE_FILE) } else { Vec::new() };
     for body in bodies {
-        commands
+        // satellites in the catalog start from the element set with their catalog number
+        let initial = body.norad_id
+            .and_then(|id| element_sets.iter().position(|elements| elements.norad_id == id))
+            .and_then(|index| Sgp4Orbit::new(&element_sets.remove(index)))
+            .and_then(|propagator| propagator.local_state(sim_epoch()).map(|local| (propagator, local)));
+        if body.norad_id.is_some() && initial.is_none() {
+            warn!("No usable element set for {} in {}", body.name, TLE_FILE);
+            continue;
+        }
+        // moons start from a state relative to their planet
+        let mut body_bundle = body.body;
+        let mut moon = None;
+        if let Some(local) = body.local {
+            let parent = match spawned.iter().find(|parent| parent.name == local.parent) {
+                Some(parent) => parent,
+                None => {
+                    warn!("{} is missing, skipping {}", local.parent, body.name);
+                    continue;
+                }
+            };
+            let position = local.position.as_vec3() * KM_TO_UNIT;
+            if position.length() < parent.radius * 2.0 {
+                // the orbit would vanish inside the parent's enlarged mesh, integrate it like a satellite instead
+                moon = Some(local);
+            } else {
+                body_bundle.offset(parent.position + position, parent.velocity + local.velocity.as_vec3() * KM_TO_UNIT * 86400.0);
+            }
+        }
+        let (position, velocity, mass) = (body_bundle.position(), body_bundle.velocity(), body_bundle.mass());
+        let entity = commands
         .spawn(SpatialBundle::from_transform(Transform::from_xyz(
             4.0, 7.0, 0.0,
         )))
-        .insert(Name::new(body.name))
+        .insert(Name::new(body.name.clone()))
         .insert(meshes.add(shape::UVSphere { radius: body.radius, ..default() }.into()))
         .insert(PickableBundle::default())
-        .insert(body.body)
+        .insert(body_bundle)
+        .insert(Radius(body.physical_radius * KM_TO_UNIT))
         .with_children(|commands| {
             commands.spawn((
                 SceneBundle {
@@ -134,6 +269,83 @@ fn sim_setup(
                     ..Default::default()
                 },
             ));
+        })
+        .id();
+        if let Some(oblateness) = body.oblateness {
+            commands.entity(entity).insert(oblateness);
+        }
+        if let Some(rotation) = body.rotation {
+            commands.entity(entity).insert(rotation);
+        }
+        if let Some(rings) = &body.rings {
+            spawn_rings(&mut commands, &mut meshes, &mut images, &mut ring_materials, entity, rings, body.radius, body.physical_radius);
+        }
+        if let Some(radiation) = body.radiation {
+            commands.entity(entity).insert(radiation);
+        }
+        if let Some(atmosphere) = body.atmosphere {
+            commands.entity(entity).insert(atmosphere);
+        }
+        if let Some(drag) = body.drag {
+            commands.entity(entity).insert(drag).insert(Decay::default());
+        }
+        match (initial, moon) {
+            (Some((propagator, local)), _) => {
+                commands.entity(entity).insert(propagator);
+                satellites.push((entity, local));
+            },
+            (None, Some(local)) => satellites.push((entity, local)),
+            // the perihelion is measured against the Sun, which means nothing for moons placed by their own state
+            (None, None) if !inside_hill_sphere(position, &spawned, sun_mass) => {
+                commands.entity(entity).insert(Perihelion::default());
+            },
+            (None, None) => {}
+        }
+        spawned.push(Spawned {
+            name: body.name,
+            entity,
+            radius: body.radius,
+            display_scale: (body.radius / (body.physical_radius * KM_TO_UNIT)) as f64,
+            position,
+            velocity,
+            mass
         });
-    }               
+    }
+    // the remaining element sets are spawned as massless satellites without a model
+    for elements in element_sets {
+        let (propagator, local) = match Sgp4Orbit::new(&elements).and_then(|propagator| propagator.local_state(sim_epoch()).map(|local| (propagator, local))) {
+            Some(initial) => initial,
+            None => {
+                warn!("Could not propagate {} to the simulation epoch", tle_name(&elements));
+                continue;
+            }
+        };
+        let entity = commands
+        .spawn(SpatialBundle::default())
+        .insert(Name::new(tle_name(&elements)))
+        .insert(meshes.add(shape::UVSphere { radius: 0.0003, ..default() }.into()))
+        .insert(PickableBundle::default())
+        .insert(BodyBundle::new(0.0, Vec3::ZERO, Vec3::ZERO))
+        .insert(Radius::default())
+        .insert(Spacecraft)
+        .insert(propagator)
+        .id();
+        if let Some(drag) = tle_drag(&elements) {
+            commands.entity(entity).insert(drag).insert(Decay::default());
+        }
+        satellites.push((entity, local));
+    }
+    for (entity, local) in satellites {
+        match spawned.iter().find(|parent| parent.name == local.parent) {
+            Some(parent) => {
+                commands.entity(entity)
+                .insert(LocalOrbit::new(parent.entity, &local, parent.display_scale))
+                .insert(Kinematic);
+            },
+            None => {
+                warn!("{} is missing, removing its satellites", local.parent);
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,235 @@
+use bevy::{prelude::*, math::{DVec3, DVec4}, render::{mesh::PrimitiveTopology, view::NoFrustumCulling}};
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+
+use crate::{bodies::KM_TO_UNIT, body::Radius, cr3bp, frame::{RotatingFrame, update_rotating_frame}, lines::line_mesh, SimState};
+
+const ORBIT_STEPS: usize = 4000;
+const TRAJECTORY_STEP: f64 = 0.002;
+/// Integration steps per drawn line segment
+const SEGMENT_STEPS: usize = 5;
+/// Trajectories leaving further than this from the secondary are cut off (nondimensional)
+const ESCAPE_DISTANCE: f64 = 0.1;
+
+pub struct ManifoldPlugin;
+
+impl Plugin for ManifoldPlugin {
+
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<Manifolds>()
+        .register_inspectable::<Manifolds>()
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(update_manifolds.after(update_rotating_frame)))
+        .add_system_set(SystemSet::on_exit(SimState::Simulation).with_system(despawn_manifolds));
+    }
+
+}
+
+/// Stable and unstable manifolds of a planar Lyapunov orbit around L1 or L2 of the [RotatingFrame] primaries
+#[derive(Resource, Inspectable)]
+pub struct Manifolds {
+    pub enabled: bool,
+    /// Collinear point the periodic orbit is built around (1 or 2)
+    pub point: u8,
+    /// Amplitude of the orbit along the x axis
+    pub amplitude_km: f32,
+    /// Number of points on the orbit the manifold trajectories start from
+    pub trajectories: usize,
+    /// Distance along the eigenvectors the starting states are perturbed by
+    pub perturbation_km: f32,
+    pub duration_days: f32,
+    pub stable: bool,
+    pub unstable: bool,
+    /// Set to recompute the manifolds on the next frame
+    pub compute: bool,
+    pub status: String,
+}
+
+impl Default for Manifolds {
+
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            point: 2,
+            amplitude_km: 200_000.0,
+            trajectories: 40,
+            perturbation_km: 1000.0,
+            duration_days: 365.0,
+            stable: true,
+            unstable: true,
+            compute: false,
+            status: String::new()
+        }
+    }
+
+}
+
+#[derive(Component)]
+struct ManifoldOverlay {
+    orbit: Handle<Mesh>,
+    stable: Handle<Mesh>,
+    unstable: Handle<Mesh>,
+    stable_entity: Entity,
+    unstable_entity: Entity,
+}
+
+fn update_manifolds(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut settings: ResMut<Manifolds>,
+    frame: Res<RotatingFrame>,
+    mut overlay: Query<(&ManifoldOverlay, &mut Transform, &mut Visibility)>,
+    mut branches: Query<&mut Visibility, Without<ManifoldOverlay>>,
+    bodies: Query<(&Name, &Radius)>
+) {
+    let (overlay, mut transform, mut visibility) = match overlay.get_single_mut() {
+        Ok(overlay) => overlay,
+        Err(_) => {
+            if settings.enabled {
+                spawn_overlay(&mut commands, &mut meshes, &mut materials);
+                settings.compute = true;
+            }
+            return;
+        }
+    };
+    visibility.is_visible = settings.enabled && frame.distance > 0.0;
+    if !visibility.is_visible {
+        return;
+    }
+    *transform = frame.synodic_transform();
+    if let Ok(mut visibility) = branches.get_mut(overlay.stable_entity) {
+        visibility.is_visible = settings.stable;
+    }
+    if let Ok(mut visibility) = branches.get_mut(overlay.unstable_entity) {
+        visibility.is_visible = settings.unstable;
+    }
+
+    if settings.compute {
+        settings.compute = false;
+        let distance_km = (frame.distance / KM_TO_UNIT) as f64;
+        let mu = frame.mu as f64;
+        let amplitude = settings.amplitude_km as f64 / distance_km;
+        let orbit = match cr3bp::lyapunov_orbit(mu, settings.point, amplitude) {
+            Some(orbit) => orbit,
+            None => {
+                settings.status = "No periodic orbit found for this amplitude".to_string();
+                return;
+            }
+        };
+        let perturbation = settings.perturbation_km as f64 / distance_km;
+        let duration = (settings.duration_days * frame.angular_velocity) as f64;
+        // trajectories hitting the secondary end there, both lengths are in units
+        let secondary_radius = bodies.iter().find(|(name, _)| name.as_str() == frame.secondary).map_or(0.0, |(_, radius)| (radius.0 / frame.distance) as f64);
+        let (orbit_points, stable, unstable) = manifold_lines(mu, &orbit, settings.trajectories.max(1), perturbation, duration, secondary_radius);
+        if let Some(mesh) = meshes.get_mut(&overlay.orbit) {
+            *mesh = line_mesh(PrimitiveTopology::LineStrip, orbit_points);
+        }
+        if let Some(mesh) = meshes.get_mut(&overlay.stable) {
+            *mesh = line_mesh(PrimitiveTopology::LineList, stable);
+        }
+        if let Some(mesh) = meshes.get_mut(&overlay.unstable) {
+            *mesh = line_mesh(PrimitiveTopology::LineList, unstable);
+        }
+        let days = orbit.period / frame.angular_velocity as f64;
+        let c = cr3bp::jacobi_constant(mu, DVec3::new(orbit.initial.x, orbit.initial.y, 0.0), DVec3::new(orbit.initial.z, orbit.initial.w, 0.0));
+        settings.status = format!("Period {:.1} days, C = {:.6}", days, c);
+    }
+}
+
+fn spawn_overlay(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>
+) {
+    let mut line = |color: Color| {
+        let mesh = meshes.add(line_mesh(PrimitiveTopology::LineList, vec![Vec3::ZERO; 2]));
+        let bundle = MaterialMeshBundle {
+            mesh: mesh.clone(),
+            material: materials.add(StandardMaterial {
+                base_color: color,
+                unlit: true,
+                ..default()
+            }),
+            ..default()
+        };
+        (mesh, bundle)
+    };
+    let (orbit, orbit_bundle) = line(Color::WHITE);
+    let (stable, stable_bundle) = line(Color::rgb(0.3, 0.9, 0.4));
+    let (unstable, unstable_bundle) = line(Color::rgb(0.95, 0.35, 0.3));
+    let orbit_entity = commands.spawn(orbit_bundle).insert(NoFrustumCulling).id();
+    let stable_entity = commands.spawn(stable_bundle).insert(NoFrustumCulling).id();
+    let unstable_entity = commands.spawn(unstable_bundle).insert(NoFrustumCulling).id();
+    commands.spawn(SpatialBundle::default())
+    .insert(Name::new("Invariant manifolds"))
+    .insert(ManifoldOverlay {
+        orbit,
+        stable,
+        unstable,
+        stable_entity,
+        unstable_entity
+    })
+    .push_children(&[orbit_entity, stable_entity, unstable_entity]);
+}
+
+/// Samples the periodic orbit and propagates the perturbed states along the monodromy eigenvectors.
+/// The unstable branches are integrated forward, the stable ones backward in time.
+/// Returns the orbit as a line strip and both manifolds as line lists in synodic coordinates.
+fn manifold_lines(mu: f64, orbit: &cr3bp::LyapunovOrbit, trajectories: usize, perturbation: f64, duration: f64, secondary_radius: f64) -> (Vec<Vec3>, Vec<Vec3>, Vec<Vec3>) {
+    let unstable_direction = cr3bp::dominant_eigenvector(orbit.monodromy);
+    let stable_direction = cr3bp::dominant_eigenvector(orbit.monodromy.inverse());
+    let secondary = DVec3::new(1.0 - mu, 0.0, 0.0);
+
+    let mut orbit_points = Vec::with_capacity(ORBIT_STEPS + 1);
+    let mut stable = Vec::new();
+    let mut unstable = Vec::new();
+    let mut s = cr3bp::PlanarState::new(orbit.initial);
+    let sample_every = (ORBIT_STEPS / trajectories).max(1);
+    for step in 0..=ORBIT_STEPS {
+        orbit_points.push(s.position().as_vec3());
+        if step % sample_every == 0 && step < ORBIT_STEPS {
+            for (direction, forward, lines) in [(unstable_direction, true, &mut unstable), (stable_direction, false, &mut stable)] {
+                // carry the eigenvector along the orbit and scale it to the requested position offset
+                let local = s.stm * direction;
+                let offset = local / DVec3::new(local.x, local.y, 0.0).length() * perturbation;
+                for sign in [1.0, -1.0] {
+                    let start = s.state + offset * sign;
+                    let dt = if forward { TRAJECTORY_STEP } else { -TRAJECTORY_STEP };
+                    propagate_branch(mu, start, dt, duration, secondary, secondary_radius, lines);
+                }
+            }
+        }
+        s = cr3bp::planar_step(mu, &s, orbit.period / ORBIT_STEPS as f64);
+    }
+    (orbit_points, stable, unstable)
+}
+
+fn propagate_branch(mu: f64, start: DVec4, dt: f64, duration: f64, secondary: DVec3, secondary_radius: f64, lines: &mut Vec<Vec3>) {
+    let mut state = start;
+    let mut last = Vec3::new(start.x as f32, start.y as f32, 0.0);
+    let mut step = 0;
+    while step as f64 * dt.abs() < duration {
+        state = cr3bp::planar_rk4(mu, state, dt);
+        step += 1;
+        let position = DVec3::new(state.x, state.y, 0.0);
+        let distance = position.distance(secondary);
+        let done = distance < secondary_radius || distance > ESCAPE_DISTANCE;
+        if step % SEGMENT_STEPS == 0 || done {
+            lines.push(last);
+            last = position.as_vec3();
+            lines.push(last);
+        }
+        if done {
+            break;
+        }
+    }
+}
+
+fn despawn_manifolds(
+    mut commands: Commands,
+    overlay: Query<Entity, With<ManifoldOverlay>>
+) {
+    for entity in overlay.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
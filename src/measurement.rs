@@ -0,0 +1,33 @@
+/// Rate of change of a quantity, measured against the value when the measurement started
+#[derive(Default)]
+pub struct Measurement<T> {
+    /// Value and simulation time when the measurement started
+    reference: Option<(T, f32)>,
+    /// Reported once the measurement ran for the minimum time
+    pub rate: Option<f32>,
+}
+
+impl<T: Copy> Measurement<T> {
+
+    pub fn restart(&mut self) {
+        self.reference = None;
+        self.rate = None;
+    }
+
+    /// Starts the measurement with `value` or, after `minimum_days` of simulation time, updates the rate with
+    /// `rate(reference, elapsed days)`
+    pub fn update(&mut self, value: T, time: f32, minimum_days: f32, rate: impl FnOnce(T, f32) -> f32) {
+        match self.reference {
+            Some((reference, start)) => {
+                let elapsed = time - start;
+                if elapsed >= minimum_days {
+                    self.rate = Some(rate(reference, elapsed));
+                }
+            },
+            None => {
+                self.reference = Some((value, time));
+            }
+        }
+    }
+
+}
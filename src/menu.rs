@@ -17,7 +17,7 @@ impl Plugin for MenuPlugin {
     }
 }
 pub fn setup(
-    camera: Query<&Camera>,
+    camera: Query<&Camera, With<PanOrbitCamera>>,
     mut commands: Commands
 ) {
     if let Err(_) = camera.get_single() {
@@ -0,0 +1,221 @@
+use bevy::prelude::*;
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+use bevy_mod_picking::PickableBundle;
+use chrono::NaiveDate;
+
+use crate::{astro::{julian_date, sim_epoch}, bodies::{AU_TO_UNIT_SCALE, KM_TO_UNIT}, body::{BodyBundle, Radius}, kepler::OrbitalElements, SimState};
+
+/// Orbit files in MPCORB format (asteroids) and JPL Small-Body Database CSV exports (asteroids and comets)
+const MPCORB_FILE: &str = "assets/minor_bodies/MPCORB.DAT";
+const SBDB_FILE: &str = "assets/minor_bodies/sbdb.csv";
+/// Geometric albedo assumed when estimating diameters from the absolute magnitude
+const ALBEDO: f64 = 0.14;
+
+pub struct MinorBodyPlugin;
+
+impl Plugin for MinorBodyPlugin {
+
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<MinorBodies>()
+        .register_inspectable::<MinorBodies>()
+        .add_system_set(SystemSet::on_enter(SimState::Simulation).with_system(spawn_minor_bodies))
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(minor_body_visibility));
+    }
+
+}
+
+#[derive(Resource, Inspectable)]
+pub struct MinorBodies {
+    pub visible: bool,
+    /// Maximum number of bodies loaded from each file, MPCORB alone has more than a million
+    pub limit: usize,
+    /// Number of bodies spawned from the files
+    pub count: usize,
+}
+
+impl Default for MinorBodies {
+
+    fn default() -> Self {
+        Self {
+            visible: true,
+            limit: 2000,
+            count: 0
+        }
+    }
+
+}
+
+/// Asteroid or comet spawned from an orbit catalog, a massless test particle
+#[derive(Component)]
+pub struct MinorBody {
+    /// Elements of the catalog, to place the body at any date without integrating it there
+    pub elements: OrbitalElements,
+}
+
+/// Entry of an orbit catalog
+pub struct MinorBodyOrbit {
+    pub name: String,
+    pub elements: OrbitalElements,
+    /// Absolute magnitude, not known for comets
+    pub magnitude: Option<f64>,
+    pub comet: bool,
+}
+
+fn spawn_minor_bodies(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut settings: ResMut<MinorBodies>
+) {
+    let mut orbits = Vec::new();
+    if let Ok(text) = std::fs::read_to_string(MPCORB_FILE) {
+        orbits.extend(parse_mpcorb(&text).into_iter().take(settings.limit));
+    }
+    if let Ok(text) = std::fs::read_to_string(SBDB_FILE) {
+        orbits.extend(parse_sbdb(&text).into_iter().take(settings.limit));
+    }
+    settings.count = orbits.len();
+    if orbits.is_empty() {
+        return;
+    }
+
+    // all bodies share one low-poly mesh instead of a model
+    let mesh = meshes.add(Mesh::from(shape::Icosphere { radius: 0.003, subdivisions: 1 }));
+    let asteroid = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.7, 0.65, 0.6),
+        unlit: true,
+        ..default()
+    });
+    let comet = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.5, 0.8, 1.0),
+        unlit: true,
+        ..default()
+    });
+    let epoch = julian_date(sim_epoch());
+    for orbit in orbits {
+        let (position, velocity) = orbit.elements.state(epoch);
+        let radius = orbit.magnitude.map_or(0.0, |magnitude| 1329.0 / ALBEDO.sqrt() * 10f64.powf(-magnitude / 5.0) / 2.0);
+        commands
+        .spawn(PbrBundle {
+            mesh: mesh.clone(),
+            material: if orbit.comet { comet.clone() } else { asteroid.clone() },
+            ..default()
+        })
+        .insert(Name::new(orbit.name))
+        .insert(PickableBundle::default())
+        .insert(BodyBundle::new(0.0, position.as_vec3() * AU_TO_UNIT_SCALE, velocity.as_vec3() * AU_TO_UNIT_SCALE))
+        .insert(Radius(radius as f32 * KM_TO_UNIT))
+        .insert(MinorBody { elements: orbit.elements });
+    }
+}
+
+fn minor_body_visibility(
+    settings: Res<MinorBodies>,
+    mut bodies: Query<&mut Visibility, With<MinorBody>>
+) {
+    if settings.is_changed() {
+        for mut visibility in bodies.iter_mut() {
+            visibility.is_visible = settings.visible;
+        }
+    }
+}
+
+/// Julian date of a packed MPC epoch like K227N (2022-07-23)
+fn unpack_epoch(packed: &str) -> Option<f64> {
+    let digit = |c: char| match c {
+        '1'..='9' => c.to_digit(10),
+        'A'..='V' => Some(c as u32 - 'A' as u32 + 10),
+        _ => None
+    };
+    let chars: Vec<char> = packed.chars().collect();
+    if chars.len() != 5 {
+        return None;
+    }
+    let century = match chars[0] {
+        'I' => 1800,
+        'J' => 1900,
+        'K' => 2000,
+        _ => return None
+    };
+    let year = century + packed.get(1..3)?.parse::<i32>().ok()?;
+    let date = NaiveDate::from_ymd_opt(year, digit(chars[3])?, digit(chars[4])?)?;
+    Some(julian_date(date.and_hms_opt(0, 0, 0)?))
+}
+
+/// Parses the fixed-width MPCORB format, header lines and malformed lines are skipped
+pub fn parse_mpcorb(text: &str) -> Vec<MinorBodyOrbit> {
+    text.lines().filter_map(|line| {
+        let field = |start: usize, end: usize| line.get(start..end).map(|field| field.trim());
+        let number = |start: usize, end: usize| field(start, end)?.parse::<f64>().ok();
+        let elements = OrbitalElements::from_mean_anomaly(
+            number(92, 103)?,
+            number(70, 79)?,
+            number(59, 68)?,
+            number(48, 57)?,
+            number(37, 46)?,
+            number(26, 35)?,
+            unpack_epoch(field(20, 25)?)?
+        );
+        // readable designation, falls back to the packed one for short lines
+        let name = field(166, 194).or_else(|| field(166, line.len())).filter(|name| !name.is_empty()).or_else(|| field(0, 7))?;
+        Some(MinorBodyOrbit {
+            name: name.to_string(),
+            elements,
+            magnitude: number(8, 13),
+            comet: false
+        })
+    }).collect()
+}
+
+/// Splits a CSV line, fields may be quoted
+fn split_csv(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            _ => fields.last_mut().unwrap().push(c)
+        }
+    }
+    fields.into_iter().map(|field| field.trim().to_string()).collect()
+}
+
+/// Parses a CSV export of the JPL Small-Body Database. Needs the columns e, i, om and w together with
+/// either a, ma and epoch or q and tp. The name is taken from full_name, name or pdes.
+pub fn parse_sbdb(text: &str) -> Vec<MinorBodyOrbit> {
+    let mut lines = text.lines();
+    let header = match lines.next() {
+        Some(header) => split_csv(header),
+        None => return Vec::new()
+    };
+    let column = |name: &str| header.iter().position(|column| column == name);
+    let name_column = column("full_name").or_else(|| column("name")).or_else(|| column("pdes"));
+    lines.filter_map(|line| {
+        let fields = split_csv(line);
+        let number = |name: &str| column(name).and_then(|index| fields.get(index)).and_then(|field| field.parse::<f64>().ok());
+        let (e, i, node, perihelion) = (number("e")?, number("i")?, number("om")?, number("w")?);
+        let elements = match (number("a"), number("ma"), number("epoch"), number("q"), number("tp")) {
+            (Some(a), Some(mean_anomaly), Some(epoch), _, _) if e < 1.0 => OrbitalElements::from_mean_anomaly(a, e, i, node, perihelion, mean_anomaly, epoch),
+            (_, _, _, Some(q), Some(tp)) => OrbitalElements {
+                perihelion_distance: q,
+                eccentricity: e,
+                inclination: i,
+                node,
+                perihelion,
+                perihelion_time: tp
+            },
+            _ => return None
+        };
+        let name = name_column.and_then(|index| fields.get(index)).cloned().unwrap_or_default();
+        // comet designations look like 1P/Halley or C/2020 F3
+        let comet = name.contains("P/") || name.contains("C/") || name.contains("D/") || name.contains("I/");
+        Some(MinorBodyOrbit {
+            name,
+            elements,
+            magnitude: number("H"),
+            comet
+        })
+    }).collect()
+}
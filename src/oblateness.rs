@@ -0,0 +1,95 @@
+use bevy::prelude::*;
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+
+use crate::{astro::radec_to_ecliptic, bodies::KM_TO_UNIT};
+
+pub struct OblatenessPlugin;
+
+impl Plugin for OblatenessPlugin {
+
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<ZonalHarmonics>()
+        .register_inspectable::<ZonalHarmonics>()
+        .register_inspectable::<Oblateness>();
+    }
+
+}
+
+/// Toggles the zonal harmonics of the [Oblateness] components in the force model
+#[derive(Resource, Inspectable)]
+pub struct ZonalHarmonics {
+    pub enabled: bool,
+}
+
+impl Default for ZonalHarmonics {
+
+    fn default() -> Self {
+        Self {
+            enabled: true
+        }
+    }
+
+}
+
+/// Zonal harmonic coefficients of a flattened body, the higher degrees are optional and can be 0
+#[derive(Component, Inspectable, Clone, Copy)]
+pub struct Oblateness {
+    pub j2: f32,
+    pub j3: f32,
+    pub j4: f32,
+    /// Reference (equatorial) radius of the coefficients in units
+    pub equatorial_radius: f32,
+    /// Unit vector along the rotation axis in the ecliptic frame
+    pub pole: Vec3,
+}
+
+impl Default for Oblateness {
+
+    fn default() -> Self {
+        Self {
+            j2: 0.0,
+            j3: 0.0,
+            j4: 0.0,
+            equatorial_radius: 0.0,
+            pole: Vec3::Z
+        }
+    }
+
+}
+
+impl Oblateness {
+
+    /// Coefficients with the radius in km and the pole given by its right ascension and declination in degrees
+    pub fn new(j2: f32, j3: f32, j4: f32, equatorial_radius_km: f32, pole_ra: f32, pole_dec: f32) -> Self {
+        Self {
+            j2,
+            j3,
+            j4,
+            equatorial_radius: equatorial_radius_km * KM_TO_UNIT,
+            pole: radec_to_ecliptic(pole_ra, pole_dec)
+        }
+    }
+
+    /// Acceleration on a body at `r` relative to the center of the flattened body on top of the point mass term,
+    /// where `gm` is the gravitational parameter of the flattened body
+    pub fn acceleration(&self, gm: f32, r: Vec3) -> Vec3 {
+        let distance_squared = r.length_squared();
+        if distance_squared <= 0.0 {
+            return Vec3::ZERO;
+        }
+        let distance = distance_squared.sqrt();
+        let z = r.dot(self.pole);
+        // sine of the latitude above the equator
+        let s = z / distance;
+        let s2 = s * s;
+        let ratio = self.equatorial_radius / distance;
+        let base = gm / distance_squared * ratio * ratio;
+
+        let j2 = (r / distance * (1.0 - 5.0 * s2) + self.pole * (2.0 * s)) * (-1.5 * self.j2);
+        let j3 = (r / distance * (s * (3.0 - 7.0 * s2)) + self.pole * (3.0 * s2 - 0.6)) * (-2.5 * self.j3 * ratio);
+        let j4 = (r / distance * (1.0 - 14.0 * s2 + 21.0 * s2 * s2) + self.pole * (s * (4.0 - 28.0 / 3.0 * s2))) * (1.875 * self.j4 * ratio * ratio);
+        (j2 + j3 + j4) * base
+    }
+
+}
@@ -0,0 +1,61 @@
+use bevy::prelude::*;
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+
+use crate::bodies::{AU_TO_UNIT_SCALE, ACCELERATION_TO_UNIT};
+
+/// Solar radiation pressure on a fully absorbing surface at 1 AU in N/m²
+const SOLAR_PRESSURE: f32 = 4.56e-6;
+
+pub struct RadiationPlugin;
+
+impl Plugin for RadiationPlugin {
+
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<SolarRadiation>()
+        .register_inspectable::<SolarRadiation>()
+        .register_inspectable::<RadiationPressure>();
+    }
+
+}
+
+/// Toggles solar radiation pressure on all bodies with a [RadiationPressure] component
+#[derive(Resource, Inspectable)]
+pub struct SolarRadiation {
+    pub enabled: bool,
+}
+
+impl Default for SolarRadiation {
+
+    fn default() -> Self {
+        Self {
+            enabled: true
+        }
+    }
+
+}
+
+/// Cannonball model of the sunlight pushing on a spacecraft
+#[derive(Component, Inspectable, Clone, Copy)]
+pub struct RadiationPressure {
+    /// Cross section facing the Sun in m²
+    pub area: f32,
+    /// Radiation pressure coefficient, 1 for a black body up to 2 for a perfect mirror
+    pub reflectivity: f32,
+}
+
+impl RadiationPressure {
+
+    /// Acceleration of a body with `mass` (10^24 kg) at `from_sun` relative to the Sun in full sunlight
+    pub fn acceleration(&self, mass: f32, from_sun: Vec3) -> Vec3 {
+        let distance = from_sun.length();
+        if mass <= 0.0 || distance <= 0.0 {
+            return Vec3::ZERO;
+        }
+        let pressure = SOLAR_PRESSURE * (AU_TO_UNIT_SCALE / distance).powi(2);
+        // mass is in 10^24 kg, keep the numbers in f32 range by dividing in two steps
+        let acceleration = pressure * self.reflectivity * self.area / mass / 1e24;
+        from_sun / distance * acceleration * ACCELERATION_TO_UNIT
+    }
+
+}
@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+
+use crate::{body::{update_bodies, Gravity, Mass, Sun, Velocity}, measurement::Measurement, ui::SimTime, SimState};
+
+const ARCSECONDS_PER_RADIAN: f32 = 206_264.8;
+const DAYS_PER_CENTURY: f32 = 36_525.0;
+/// Shortest measurement before a precession rate is reported
+const MINIMUM_MEASUREMENT_DAYS: f32 = 30.0;
+/// Smallest exaggeration that lifts the correction above the rounding noise of the f32 state
+pub const RESOLVABLE_FACTOR: f32 = 1000.0;
+
+pub struct RelativityPlugin;
+
+impl Plugin for RelativityPlugin {
+
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<Relativity>()
+        .register_inspectable::<Relativity>()
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(measure_precession.after(update_bodies)));
+    }
+
+}
+
+/// Post-newtonian correction of the acceleration towards the Sun.
+/// The real correction for Mercury is ~1e-8 of the newtonian acceleration, which is below the
+/// resolution of the f32 state, so `factor` scales it up to make the effect visible.
+#[derive(Resource, Inspectable)]
+pub struct Relativity {
+    pub enabled: bool,
+    pub factor: f32,
+}
+
+impl Default for Relativity {
+
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            factor: 1.0
+        }
+    }
+
+}
+
+/// Measures how fast the perihelion of a body orbiting the Sun moves, using the direction of its eccentricity vector
+#[derive(Component, Default)]
+pub struct Perihelion {
+    pub eccentricity: f32,
+    /// Precession of the eccentricity vector in arcseconds per century
+    pub precession: Measurement<Vec3>,
+}
+
+fn measure_precession(
+    g: Res<Gravity>,
+    sim_time: Res<SimTime>,
+    sun: Query<(&Transform, &Velocity, &Mass), With<Sun>>,
+    mut bodies: Query<(&Transform, &Velocity, &Mass, &mut Perihelion), Without<Sun>>
+) {
+    let (sun_transform, sun_velocity, sun_mass) = match sun.get_single() {
+        Ok(sun) => sun,
+        Err(_) => return
+    };
+    for (transform, velocity, mass, mut perihelion) in bodies.iter_mut() {
+        let r = transform.translation - sun_transform.translation;
+        let v = velocity.0 - sun_velocity.0;
+        let gm = g.0 * (sun_mass.0 + mass.0);
+        let distance = r.length();
+        if distance <= 0.0 || gm <= 0.0 {
+            continue;
+        }
+        let eccentricity = (r * (v.length_squared() - gm / distance) - v * r.dot(v)) / gm;
+        perihelion.eccentricity = eccentricity.length();
+        // signed angle around the orbit normal, positive in the direction of motion
+        let normal = r.cross(v).normalize_or_zero();
+        perihelion.precession.update(eccentricity, sim_time.0, MINIMUM_MEASUREMENT_DAYS, |reference, elapsed| {
+            let angle = reference.cross(eccentricity).dot(normal).atan2(reference.dot(eccentricity));
+            angle / elapsed * DAYS_PER_CENTURY * ARCSECONDS_PER_RADIAN
+        });
+    }
+}
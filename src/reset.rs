@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 use bevy_mod_picking::{PickableBundle, Selection};
 
-use crate::{SimState, skybox::Skybox, speed::Speed, body::Pause, ui::SimTime};
+use crate::{SimState, skybox::Skybox, speed::Speed, body::Pause, eclipse::Eclipses, eclipse_search::EclipseSearch, ui::{SimTime, ImpactLog, EclipseLog}};
 
 pub struct ResetPlugin;
 
@@ -21,6 +21,10 @@ fn clean_up(
     mut speed: ResMut<Speed>,
     mut pause: ResMut<Pause>,
     mut sim_time: ResMut<SimTime>,
+    mut impact_log: ResMut<ImpactLog>,
+    mut eclipse_log: ResMut<EclipseLog>,
+    mut eclipses: ResMut<Eclipses>,
+    mut eclipse_search: ResMut<EclipseSearch>,
     mut commands: Commands
 ) {
     for (entity, _, _, _, _) in entities.iter() {
@@ -29,6 +33,10 @@ fn clean_up(
     speed.0 = 1.0;
     pause.0 = false;
     sim_time.0 = 0.0;
+    impact_log.0.clear();
+    eclipse_log.0.clear();
+    eclipses.clear();
+    eclipse_search.clear();
 }
 
 fn switch_to_menu(
@@ -0,0 +1,250 @@
+use bevy::{
+    pbr::{MaterialPipeline, MaterialPipelineKey},
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        mesh::{Indices, MeshVertexBufferLayout, PrimitiveTopology},
+        render_resource::{AsBindGroup, Extent3d, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError, TextureDimension, TextureFormat},
+    },
+};
+
+use crate::{astro::{julian_date, sim_date}, body::Sun, rotation::Rotation, ui::SimTime, SimState};
+
+/// Texels of the radial profile texture
+const PROFILE_RESOLUTION: usize = 2048;
+const SEGMENTS: usize = 256;
+
+pub struct RingPlugin;
+
+impl Plugin for RingPlugin {
+
+    fn build(&self, app: &mut App) {
+        app
+        .add_plugin(MaterialPlugin::<RingMaterial>::default())
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(update_rings));
+    }
+
+}
+
+/// Ring system of a planet in its equatorial plane, made of bands with a constant opacity
+#[derive(Clone)]
+pub struct RingSystem {
+    pub color: Color,
+    /// Radii in km
+    pub inner_radius: f32,
+    pub outer_radius: f32,
+    /// Inner radius, outer radius (km) and opacity of each band, gaps are transparent
+    pub bands: Vec<(f32, f32, f32)>,
+}
+
+impl RingSystem {
+
+    pub fn saturn() -> Self {
+        Self {
+            color: Color::rgb(0.86, 0.79, 0.66),
+            inner_radius: 74_658.0,
+            outer_radius: 140_220.0,
+            bands: vec![
+                (74_658.0, 92_000.0, 0.12),  // C ring
+                (92_000.0, 99_000.0, 0.7),   // inner B ring
+                (99_000.0, 117_580.0, 0.95), // outer B ring
+                (117_580.0, 122_170.0, 0.1), // Cassini Division
+                (122_170.0, 133_426.0, 0.6), // A ring
+                (133_751.0, 136_487.0, 0.55),
+                (136_522.0, 136_775.0, 0.5),
+                (140_130.0, 140_220.0, 0.3), // F ring
+            ]
+        }
+    }
+
+    pub fn uranus() -> Self {
+        Self {
+            color: Color::rgb(0.25, 0.25, 0.25),
+            inner_radius: 41_800.0,
+            outer_radius: 51_200.0,
+            bands: vec![
+                (41_835.0, 41_839.0, 0.3),   // 6
+                (42_232.0, 42_236.0, 0.4),   // 5
+                (42_569.0, 42_573.0, 0.3),   // 4
+                (44_713.0, 44_723.0, 0.4),   // α
+                (45_655.0, 45_667.0, 0.3),   // β
+                (47_174.0, 47_178.0, 0.2),   // η
+                (47_624.0, 47_630.0, 0.6),   // γ
+                (48_297.0, 48_303.0, 0.4),   // δ
+                (50_022.0, 50_024.0, 0.2),   // λ
+                (51_120.0, 51_178.0, 0.8),   // ε
+            ]
+        }
+    }
+
+    /// Opacity sampled across the rings, each texel averages the bands it covers so narrow rings stay visible
+    pub fn profile(&self, resolution: usize) -> Vec<f32> {
+        let width = (self.outer_radius - self.inner_radius) / resolution as f32;
+        (0..resolution).map(|i| {
+            let start = self.inner_radius + i as f32 * width;
+            let end = start + width;
+            self.bands.iter().map(|(inner, outer, opacity)| {
+                (outer.min(end) - inner.max(start)).max(0.0) * opacity
+            }).sum::<f32>() / width
+        }).collect()
+    }
+
+}
+
+/// Ring mesh or the shell around the planet receiving the ring shadow, child of the planet
+#[derive(Component)]
+pub struct Ring;
+
+/// Draws the rings with the planet's shadow on them, or with `shadow` set, the rings' shadow on a shell around the planet.
+/// The positions are updated every frame as the shadows are computed analytically in the shader.
+#[derive(AsBindGroup, TypeUuid, Debug, Clone)]
+#[uuid = "2d4b5c0e-8f3a-4c57-9d1e-6a7b8c9d0e1f"]
+pub struct RingMaterial {
+    #[uniform(0)]
+    pub color: Color,
+    /// Center of the planet in world space
+    #[uniform(0)]
+    pub center: Vec3,
+    /// Radius of the planet's mesh
+    #[uniform(0)]
+    pub planet_radius: f32,
+    #[uniform(0)]
+    pub sun: Vec3,
+    #[uniform(0)]
+    pub inner_radius: f32,
+    /// Normal of the ring plane in world space
+    #[uniform(0)]
+    pub normal: Vec3,
+    #[uniform(0)]
+    pub outer_radius: f32,
+    /// 1 for the shadow shell, 0 for the rings
+    #[uniform(0)]
+    pub shadow: f32,
+    #[texture(1)]
+    #[sampler(2)]
+    pub profile: Handle<Image>,
+}
+
+impl Material for RingMaterial {
+
+    fn fragment_shader() -> ShaderRef {
+        "shaders/rings.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayout,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        // the rings are seen from both sides
+        descriptor.primitive.cull_mode = None;
+        Ok(())
+    }
+
+}
+
+/// Flat annulus in the xy plane with u running from the inner to the outer edge
+pub fn ring_mesh(inner_radius: f32, outer_radius: f32, segments: usize) -> Mesh {
+    let mut positions = Vec::with_capacity(2 * (segments + 1));
+    let mut uvs = Vec::with_capacity(2 * (segments + 1));
+    for i in 0..=segments {
+        let angle = i as f32 / segments as f32 * std::f32::consts::TAU;
+        let (sin, cos) = angle.sin_cos();
+        positions.push([inner_radius * cos, inner_radius * sin, 0.0]);
+        positions.push([outer_radius * cos, outer_radius * sin, 0.0]);
+        uvs.push([0.0, i as f32 / segments as f32]);
+        uvs.push([1.0, i as f32 / segments as f32]);
+    }
+    let mut indices = Vec::with_capacity(6 * segments);
+    for i in 0..segments as u32 {
+        let (inner, outer) = (2 * i, 2 * i + 1);
+        indices.extend_from_slice(&[inner, outer, inner + 2, outer, outer + 2, inner + 2]);
+    }
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; positions.len()]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+/// Spawns the rings and the shadow shell as children of `planet`, with the radii scaled like the planet's mesh
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_rings(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    images: &mut Assets<Image>,
+    materials: &mut Assets<RingMaterial>,
+    planet: Entity,
+    rings: &RingSystem,
+    radius: f32,
+    physical_radius: f32
+) {
+    let scale = radius / physical_radius;
+    let data = rings.profile(PROFILE_RESOLUTION).iter()
+        .flat_map(|opacity| [255, 255, 255, (opacity.clamp(0.0, 1.0) * 255.0) as u8])
+        .collect();
+    let profile = images.add(Image::new(
+        Extent3d { width: PROFILE_RESOLUTION as u32, height: 1, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8Unorm
+    ));
+    let material = RingMaterial {
+        color: rings.color,
+        center: Vec3::ZERO,
+        planet_radius: radius,
+        sun: Vec3::ZERO,
+        inner_radius: rings.inner_radius * scale,
+        normal: Vec3::Z,
+        outer_radius: rings.outer_radius * scale,
+        shadow: 0.0,
+        profile
+    };
+    commands.entity(planet).with_children(|commands| {
+        commands.spawn(MaterialMeshBundle {
+            mesh: meshes.add(ring_mesh(rings.inner_radius * scale, rings.outer_radius * scale, SEGMENTS)),
+            material: materials.add(material.clone()),
+            ..default()
+        })
+        .insert(Ring);
+        commands.spawn(MaterialMeshBundle {
+            mesh: meshes.add(Mesh::from(shape::UVSphere { radius: radius * 1.01, ..default() })),
+            material: materials.add(RingMaterial {
+                shadow: 1.0,
+                ..material
+            }),
+            ..default()
+        })
+        .insert(Ring);
+    });
+}
+
+fn update_rings(
+    sim_time: Res<SimTime>,
+    mut materials: ResMut<Assets<RingMaterial>>,
+    sun: Query<&Transform, (With<Sun>, Without<Ring>)>,
+    planets: Query<(&Transform, &Rotation), Without<Ring>>,
+    mut rings: Query<(&Parent, &Handle<RingMaterial>, &mut Transform), With<Ring>>
+) {
+    let sun = sun.get_single().map_or(Vec3::ZERO, |sun| sun.translation);
+    let time = julian_date(sim_date(sim_time.0));
+    for (parent, handle, mut transform) in rings.iter_mut() {
+        let (planet, rotation) = match planets.get(parent.get()) {
+            Ok(planet) => planet,
+            Err(_) => continue
+        };
+        // the rings lie in the equatorial plane, their rotation doesn't matter
+        transform.rotation = rotation.equator(time).as_f32();
+        if let Some(material) = materials.get_mut(handle) {
+            material.center = planet.translation;
+            material.sun = sun;
+            material.normal = transform.rotation * Vec3::Z;
+        }
+    }
+}
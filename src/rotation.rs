@@ -0,0 +1,93 @@
+use bevy::{prelude::*, math::{DQuat, DVec3}};
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+
+use crate::{astro::{julian_date, sim_date, J2000, OBLIQUITY}, ui::SimTime, SimState};
+
+pub struct RotationPlugin;
+
+impl Plugin for RotationPlugin {
+
+    fn build(&self, app: &mut App) {
+        app
+        .register_inspectable::<Rotation>()
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(rotate_models));
+    }
+
+}
+
+/// Orientation of a body after the IAU WGCCRE model: the pole's right ascension and declination (ICRF) drift
+/// linearly per julian century and the prime meridian turns at a constant rate, all angles in degrees.
+/// Periodic terms are left out.
+#[derive(Component, Inspectable, Clone, Copy, Default)]
+pub struct Rotation {
+    pub pole_ra: f64,
+    pub pole_ra_rate: f64,
+    pub pole_dec: f64,
+    pub pole_dec_rate: f64,
+    /// Prime meridian at J2000
+    pub meridian: f64,
+    /// Rotation of the prime meridian per day, negative for retrograde rotators
+    pub meridian_rate: f64,
+}
+
+impl Rotation {
+
+    pub fn new(pole_ra: f64, pole_ra_rate: f64, pole_dec: f64, pole_dec_rate: f64, meridian: f64, meridian_rate: f64) -> Self {
+        Self {
+            pole_ra,
+            pole_ra_rate,
+            pole_dec,
+            pole_dec_rate,
+            meridian,
+            meridian_rate
+        }
+    }
+
+    /// Sidereal rotation period in days
+    pub fn period(&self) -> f64 {
+        360.0 / self.meridian_rate.abs()
+    }
+
+    /// Rotation from the body-fixed frame (z along the pole, x through the prime meridian) to the ecliptic frame
+    /// at the julian date `time`
+    pub fn orientation(&self, time: f64) -> DQuat {
+        let meridian = (self.meridian + self.meridian_rate * (time - J2000)).rem_euclid(360.0);
+        self.equator(time) * DQuat::from_rotation_z(meridian.to_radians())
+    }
+
+    /// Rotation from the body's equatorial frame (z along the pole, x towards the ascending node of the equator
+    /// on the ICRF equator) to the ecliptic frame at the julian date `time`
+    pub fn equator(&self, time: f64) -> DQuat {
+        let centuries = (time - J2000) / 36_525.0;
+        let ra = self.pole_ra + self.pole_ra_rate * centuries;
+        let dec = self.pole_dec + self.pole_dec_rate * centuries;
+        DQuat::from_rotation_x(-(OBLIQUITY as f64).to_radians())
+            * DQuat::from_rotation_z((ra + 90.0).to_radians())
+            * DQuat::from_rotation_x((90.0 - dec).to_radians())
+    }
+
+    /// Unit vector along the pole in the ecliptic frame
+    pub fn pole(&self, time: f64) -> DVec3 {
+        self.orientation(time) * DVec3::Z
+    }
+
+}
+
+/// Turns the model (the scene child) of every body with a [Rotation], the models are y-up with the prime meridian
+/// facing +x so they are first tilted onto the body-fixed z axis
+fn rotate_models(
+    sim_time: Res<SimTime>,
+    bodies: Query<(&Rotation, &Children)>,
+    mut models: Query<&mut Transform, With<Handle<Scene>>>
+) {
+    let time = julian_date(sim_date(sim_time.0));
+    let model_to_body = DQuat::from_rotation_x(std::f64::consts::FRAC_PI_2);
+    for (rotation, children) in bodies.iter() {
+        let orientation = (rotation.orientation(time) * model_to_body).as_f32();
+        for child in children.iter() {
+            if let Ok(mut transform) = models.get_mut(*child) {
+                transform.rotation = orientation;
+            }
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use bevy::{prelude::*, math::DVec3};
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+
+use crate::{astro::sim_date, bodies::KM_TO_UNIT, body::{update_bodies, Gravity, Kinematic, Mass, Pause, Radius, Sun, Velocity}, collision::{CollisionEvent, CollisionOutcome, Collisions}, drag::{Atmosphere, AtmosphericDrag, Drag}, eclipse::illumination, oblateness::{Oblateness, ZonalHarmonics}, radiation::{RadiationPressure, SolarRadiation}, speed::Speed, tle::Sgp4Orbit, ui::SimTime, SimState};
+
+pub struct SatellitePlugin;
+
+impl Plugin for SatellitePlugin {
+
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<Satellites>()
+        .register_inspectable::<Satellites>()
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(update_satellites.after(update_bodies)));
+    }
+
+}
+
+/// Settings of the integration of satellites in the frame of their parent body
+#[derive(Resource, Inspectable)]
+pub struct Satellites {
+    /// Spawn the satellites of the body list and the TLE file, takes effect when the simulation is (re)started
+    pub enabled: bool,
+    /// Draw satellite orbits scaled up by the same factor as their parent's mesh so they don't vanish inside it
+    pub exaggerate: bool,
+    /// Integration steps per orbital period
+    pub steps_per_orbit: u32,
+    pub max_substeps: u32,
+    /// Follow the SGP4 prediction of satellites loaded from TLEs instead of integrating them
+    pub sgp4: bool,
+}
+
+impl Default for Satellites {
+
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            exaggerate: true,
+            steps_per_orbit: 500,
+            max_substeps: 50_000,
+            sgp4: false
+        }
+    }
+
+}
+
+/// Initial state of a satellite relative to its parent
+pub struct LocalState {
+    pub parent: String,
+    /// Position in km
+    pub position: DVec3,
+    /// Velocity in km/s
+    pub velocity: DVec3,
+}
+
+/// Satellite integrated in f64 relative to its parent with much finer steps than the planets.
+/// Satellites are [Kinematic], the transform follows the parent and the local state.
+#[derive(Component)]
+pub struct LocalOrbit {
+    pub parent: Entity,
+    /// Position relative to the parent in units
+    pub position: DVec3,
+    /// Velocity relative to the parent in units/day
+    pub velocity: DVec3,
+    /// Ratio of the parent's mesh radius to its physical radius
+    pub display_scale: f64,
+}
+
+impl LocalOrbit {
+
+    pub fn new(parent: Entity, state: &LocalState, display_scale: f64) -> Self {
+        let km_to_unit = KM_TO_UNIT as f64;
+        Self {
+            parent,
+            position: state.position * km_to_unit,
+            velocity: state.velocity * km_to_unit * 86400.0,
+            display_scale
+        }
+    }
+
+}
+
+/// Everything acting on a satellite while it is integrated, frozen for the duration of a frame
+struct Environment {
+    gm: f64,
+    radius: f32,
+    oblateness: Option<Oblateness>,
+    drag: Option<(Atmosphere, Drag)>,
+    /// Radiation model, mass of the satellite and the Sun's position and radius relative to the parent
+    radiation: Option<(RadiationPressure, f32, DVec3, f32)>,
+    /// Gravitational parameters and positions relative to the parent of all other bodies
+    tidal: Vec<(f64, DVec3)>,
+}
+
+impl Environment {
+
+    fn acceleration(&self, r: DVec3, v: DVec3) -> DVec3 {
+        let distance = r.length();
+        let mut a = -r * (self.gm / distance.powi(3));
+        // the perturbations are orders of magnitude smaller, f32 is enough for them
+        if let Some(oblateness) = self.oblateness {
+            a += oblateness.acceleration(self.gm as f32, r.as_vec3()).as_dvec3();
+        }
+        if let Some((atmosphere, drag)) = self.drag {
+            a += atmosphere.acceleration(&drag, self.radius, r.as_vec3(), v.as_vec3()).as_dvec3();
+        }
+        if let Some((radiation, mass, sun, sun_radius)) = self.radiation {
+            let light = illumination(r.as_vec3(), (sun.as_vec3(), sun_radius), (Vec3::ZERO, self.radius));
+            if light > 0.0 {
+                a += (radiation.acceleration(mass, (r - sun).as_vec3()) * light).as_dvec3();
+            }
+        }
+        // difference between the pull on the satellite and on the parent
+        for (gm, position) in self.tidal.iter() {
+            let d = *position - r;
+            a += (d / d.length().powi(3) - *position / position.length().powi(3)) * *gm;
+        }
+        a
+    }
+
+    fn rk4(&self, r: DVec3, v: DVec3, h: f64) -> (DVec3, DVec3) {
+        let a1 = self.acceleration(r, v);
+        let (r2, v2) = (r + v * (h / 2.0), v + a1 * (h / 2.0));
+        let a2 = self.acceleration(r2, v2);
+        let (r3, v3) = (r + v2 * (h / 2.0), v + a2 * (h / 2.0));
+        let a3 = self.acceleration(r3, v3);
+        let (r4, v4) = (r + v3 * h, v + a3 * h);
+        let a4 = self.acceleration(r4, v4);
+        (
+            r + (v + v2 * 2.0 + v3 * 2.0 + v4) * (h / 6.0),
+            v + (a1 + a2 * 2.0 + a3 * 2.0 + a4) * (h / 6.0)
+        )
+    }
+
+}
+
+pub fn update_satellites(
+    mut commands: Commands,
+    g: Res<Gravity>,
+    settings: Res<Satellites>,
+    (harmonics, radiation, drag): (Res<ZonalHarmonics>, Res<SolarRadiation>, Res<AtmosphericDrag>),
+    time: Res<Time>,
+    speed: Res<Speed>,
+    pause: Res<Pause>,
+    sim_time: Res<SimTime>,
+    collisions: Res<Collisions>,
+    mut bodies: Query<(Entity, &Name, &Transform, &mut Velocity, &mut Mass, Option<&Radius>, Option<&Oblateness>, Option<&Atmosphere>, Option<&Sun>), Without<LocalOrbit>>,
+    mut satellites: Query<(Entity, &Name, &mut LocalOrbit, &mut Transform, &mut Velocity, &Mass, Option<&RadiationPressure>, Option<&Drag>, Option<&Sgp4Orbit>)>,
+    mut events: EventWriter<CollisionEvent>
+) {
+    let dt = (time.delta_seconds() * speed.0) as f64;
+    // satellites merged into their parent, applied once all satellites are moved
+    let mut merged: Vec<(Entity, f32, Vec3)> = Vec::new();
+    for (entity, name, mut orbit, mut transform, mut velocity, mass, radiation_pressure, drag_model, propagator) in satellites.iter_mut() {
+        let (_, parent_name, parent_transform, parent_velocity, parent_mass, parent_radius, oblateness, atmosphere, _) = match bodies.get(orbit.parent) {
+            Ok(parent) => parent,
+            Err(_) => {
+                // the parent is gone, continue as a regular body
+                commands.entity(entity).remove::<LocalOrbit>().remove::<Kinematic>();
+                continue;
+            }
+        };
+        let parent_position = parent_transform.translation.as_dvec3();
+        let radius = parent_radius.map_or(0.0, |radius| radius.0);
+        let km_to_unit = KM_TO_UNIT as f64;
+        let prediction = match (settings.sgp4, propagator) {
+            (true, Some(propagator)) => propagator.state(sim_date(sim_time.0)),
+            _ => None
+        };
+        if let Some((position, velocity)) = prediction {
+            orbit.position = position * km_to_unit;
+            orbit.velocity = velocity * km_to_unit * 86400.0;
+        } else {
+            let mut environment = Environment {
+                gm: (g.0 * parent_mass.0) as f64,
+                radius,
+                oblateness: if harmonics.enabled { oblateness.copied() } else { None },
+                drag: match (atmosphere, drag_model) {
+                    (Some(atmosphere), Some(drag_model)) if drag.enabled => Some((*atmosphere, *drag_model)),
+                    _ => None
+                },
+                radiation: None,
+                tidal: Vec::new()
+            };
+            for (other, _, other_transform, _, other_mass, other_radius, _, _, sun) in bodies.iter() {
+                if other == orbit.parent || other_mass.0 <= 0.0 {
+                    continue;
+                }
+                let position = other_transform.translation.as_dvec3() - parent_position;
+                environment.tidal.push(((g.0 * other_mass.0) as f64, position));
+                if let (Some(_), Some(radiation_pressure)) = (sun, radiation_pressure) {
+                    if radiation.enabled {
+                        environment.radiation = Some((*radiation_pressure, mass.0, position, other_radius.map_or(0.0, |radius| radius.0)));
+                    }
+                }
+            }
+
+            if !pause.0 && dt != 0.0 && environment.gm > 0.0 {
+                let period = std::f64::consts::TAU * (orbit.position.length().powi(3) / environment.gm).sqrt();
+                let substeps = ((dt.abs() / period * settings.steps_per_orbit as f64).ceil() as u32).clamp(1, settings.max_substeps.max(1));
+                let h = dt / substeps as f64;
+                let (mut r, mut v) = (orbit.position, orbit.velocity);
+                let mut impact = false;
+                for _ in 0..substeps {
+                    (r, v) = environment.rk4(r, v, h);
+                    if collisions.enabled && r.length() < radius as f64 {
+                        impact = true;
+                        break;
+                    }
+                }
+                if impact {
+                    events.send(CollisionEvent {
+                        first: name.to_string(),
+                        second: parent_name.to_string(),
+                        outcome: collisions.outcome,
+                        speed: v.length() as f32 / KM_TO_UNIT / 86400.0,
+                        time: sim_time.0
+                    });
+                    match collisions.outcome {
+                        CollisionOutcome::Merge => {
+                            merged.push((orbit.parent, mass.0, (parent_velocity.0 + v.as_vec3()) * mass.0));
+                            commands.entity(entity).despawn_recursive();
+                            continue;
+                        },
+                        CollisionOutcome::Bounce => {
+                            // the parent is so much heavier that the satellite takes the whole impulse
+                            let normal = r.normalize();
+                            let approach = v.dot(normal);
+                            if approach < 0.0 {
+                                v -= normal * (1.0 + collisions.restitution as f64) * approach;
+                            }
+                            r = normal * radius as f64;
+                        },
+                        CollisionOutcome::Despawn => {
+                            commands.entity(entity).despawn_recursive();
+                            continue;
+                        }
+                    }
+                }
+                orbit.position = r;
+                orbit.velocity = v;
+            }
+        }
+
+        let scale = if settings.exaggerate { orbit.display_scale } else { 1.0 };
+        transform.translation = (parent_position + orbit.position * scale).as_vec3();
+        velocity.0 = parent_velocity.0 + orbit.velocity.as_vec3();
+    }
+    for (parent, mass, momentum) in merged {
+        if let Ok((_, _, _, mut velocity, mut parent_mass, _, _, _, _)) = bodies.get_mut(parent) {
+            let total_mass = parent_mass.0 + mass;
+            if total_mass > 0.0 {
+                velocity.0 = (velocity.0 * parent_mass.0 + momentum) / total_mass;
+                parent_mass.0 = total_mass;
+            }
+        }
+    }
+}
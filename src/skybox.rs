@@ -12,14 +12,23 @@ use bevy::{
             BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType,
             OwnedBindingResource, PreparedBindGroup, RenderPipelineDescriptor, SamplerBindingType,
             ShaderRef, ShaderStages, SpecializedMeshPipelineError, TextureSampleType,
-            TextureViewDescriptor, TextureViewDimension,
+            TextureViewDescriptor, TextureViewDimension, Extent3d, TextureDimension,
         },
         renderer::RenderDevice,
         texture::FallbackImage,
     },
 };
 
-use crate::SimState;
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+
+use crate::{astro::OBLIQUITY, SimState};
+
+/// Rotation from J2000 equatorial to galactic coordinates (the rows are the galactic axes)
+const EQUATORIAL_TO_GALACTIC: [[f32; 3]; 3] = [
+    [-0.054_875_56, -0.873_437_1, -0.483_835_02],
+    [0.494_109_43, -0.444_829_63, 0.746_982_24],
+    [-0.867_666_15, -0.198_076_37, 0.455_983_78],
+];
 
 pub struct SkyboxPlugin;
 
@@ -27,9 +36,12 @@ impl Plugin for SkyboxPlugin {
     
     fn build(&self, app: &mut bevy::prelude::App) {
         app
+        .init_resource::<SkyboxOrientation>()
+        .register_inspectable::<SkyboxOrientation>()
         .add_startup_system(setup)
         .add_plugin(MaterialPlugin::<CubemapMaterial>::default())
-        .add_system(asset_loaded);
+        .add_system(asset_loaded)
+        .add_system(orient_skybox);
     }
     
 }
@@ -37,6 +49,64 @@ impl Plugin for SkyboxPlugin {
 #[derive(Component)]
 pub struct Skybox;
 
+/// Celestial frame the background image is drawn in. The image's up direction is the frame's north pole
+/// and the center of a panorama (the cube's -z face) its zero longitude, with longitude increasing to the left.
+#[derive(Inspectable, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SkyFrame {
+    Ecliptic,
+    Equatorial,
+    Galactic,
+}
+
+impl SkyFrame {
+
+    /// Rotation from the frame (z towards the north pole, x towards zero longitude) to the ecliptic frame of the simulation
+    pub fn to_ecliptic(&self) -> Mat3 {
+        let equatorial_to_ecliptic = Mat3::from_rotation_x(-OBLIQUITY.to_radians());
+        match self {
+            SkyFrame::Ecliptic => Mat3::IDENTITY,
+            SkyFrame::Equatorial => equatorial_to_ecliptic,
+            SkyFrame::Galactic => equatorial_to_ecliptic * Mat3::from_cols_array_2d(&EQUATORIAL_TO_GALACTIC)
+        }
+    }
+
+    /// Rotation from the frame of the image to the ecliptic frame of the simulation
+    pub fn rotation(&self) -> Quat {
+        // image up (y) to the pole (z), the center of the image (-z) to zero longitude (x)
+        let image_to_frame = Mat3::from_cols(Vec3::NEG_Y, Vec3::Z, Vec3::NEG_X);
+        Quat::from_mat3(&(self.to_ecliptic() * image_to_frame))
+    }
+
+}
+
+/// Alignment of the background with the celestial sphere
+#[derive(Resource, Inspectable)]
+pub struct SkyboxOrientation {
+    pub frame: SkyFrame,
+    /// Additional rotation about the frame's pole in degrees, to match images with another zero longitude
+    pub longitude_offset: f32,
+}
+
+impl Default for SkyboxOrientation {
+
+    fn default() -> Self {
+        Self {
+            frame: SkyFrame::Ecliptic,
+            longitude_offset: 0.0
+        }
+    }
+
+}
+
+fn orient_skybox(
+    orientation: Res<SkyboxOrientation>,
+    mut skybox: Query<&mut Transform, With<Skybox>>
+) {
+    for mut transform in skybox.iter_mut() {
+        transform.rotation = orientation.frame.rotation() * Quat::from_rotation_y(orientation.longitude_offset.to_radians());
+    }
+}
+
 #[derive(Resource)]
 struct Cubemap {
     is_loaded: bool,
@@ -66,6 +136,14 @@ fn asset_loaded(
     {
         println!("Loading skybox");
         let mut image = images.get_mut(&cubemap.image_handle).unwrap();
+        // a 2:1 image is a panorama, the faces of the cube map are sampled from it
+        let size = image.texture_descriptor.size;
+        if image.texture_descriptor.array_layer_count() == 1 && size.width == 2 * size.height {
+            match equirectangular_to_cube_faces(image) {
+                Some(faces) => *image = faces,
+                None => warn!("Unsupported format of the panorama {:?}", image.texture_descriptor.format)
+            }
+        }
         // NOTE: PNGs do not have any metadata that could indicate they contain a cubemap texture,
         // so they appear as one texture. The following code reconfigures the texture as necessary.
         if image.texture_descriptor.array_layer_count() == 1 {
@@ -101,6 +179,47 @@ fn asset_loaded(
     }
 }
 
+/// Resamples an equirectangular panorama into the six faces of a cube map stacked vertically (+x, -x, +y, -y, +z, -z)
+/// with nearest neighbour sampling, None for formats that don't have 4 bytes per pixel
+fn equirectangular_to_cube_faces(panorama: &Image) -> Option<Image> {
+    let format = panorama.texture_descriptor.format;
+    if format.describe().block_size != 4 {
+        return None;
+    }
+    let (width, height) = (panorama.texture_descriptor.size.width as usize, panorama.texture_descriptor.size.height as usize);
+    let face = width / 4;
+    let mut data = Vec::with_capacity(6 * face * face * 4);
+    for side in 0..6 {
+        for y in 0..face {
+            for x in 0..face {
+                let u = (x as f32 + 0.5) / face as f32 * 2.0 - 1.0;
+                let v = (y as f32 + 0.5) / face as f32 * 2.0 - 1.0;
+                let direction = match side {
+                    0 => Vec3::new(1.0, -v, -u),
+                    1 => Vec3::new(-1.0, -v, u),
+                    2 => Vec3::new(u, 1.0, v),
+                    3 => Vec3::new(u, -1.0, -v),
+                    4 => Vec3::new(u, -v, 1.0),
+                    _ => Vec3::new(-u, -v, -1.0)
+                }.normalize();
+                // the shader samples the cube with z flipped
+                let longitude = (-direction.x).atan2(direction.z);
+                let latitude = direction.y.asin();
+                let column = (((0.5 - longitude / std::f32::consts::TAU) * width as f32) as usize).min(width - 1);
+                let row = (((0.5 - latitude / std::f32::consts::PI) * height as f32) as usize).min(height - 1);
+                let index = (row * width + column) * 4;
+                data.extend_from_slice(&panorama.data[index..index + 4]);
+            }
+        }
+    }
+    Some(Image::new(
+        Extent3d { width: face as u32, height: 6 * face as u32, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        data,
+        format
+    ))
+}
+
 #[derive(Debug, Clone, TypeUuid)]
 #[uuid = "9509a0f8-3c05-48ee-a13e-a93226c7f488"]
 pub struct CubemapMaterial {
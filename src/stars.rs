@@ -0,0 +1,199 @@
+use bevy::{
+    pbr::{MaterialPipeline, MaterialPipelineKey, NotShadowCaster},
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        mesh::{Indices, MeshVertexBufferLayout, PrimitiveTopology},
+        render_resource::{AsBindGroup, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError},
+        view::NoFrustumCulling,
+    },
+};
+
+use crate::{astro::radec_to_ecliptic, camera::PanOrbitCamera};
+
+/// Full HYG database, used instead of the bundled bright stars if present
+const HYG_FILE: &str = "assets/stars/hygdata_v3.csv";
+/// Hand-picked bright stars down to about magnitude 3.3, far short of [MAGNITUDE_LIMIT]
+const BRIGHT_STARS_FILE: &str = "assets/stars/bright_stars.csv";
+/// Faintest visual magnitude drawn
+const MAGNITUDE_LIMIT: f32 = 6.5;
+/// Radius of the sphere the stars are drawn on, inside the camera's far plane
+const SPHERE_RADIUS: f32 = 900.0;
+/// Angular radius of a magnitude 0 star in radians
+const STAR_SIZE: f32 = 0.004;
+
+pub struct StarPlugin;
+
+impl Plugin for StarPlugin {
+
+    fn build(&self, app: &mut App) {
+        app
+        .add_plugin(MaterialPlugin::<StarMaterial>::default())
+        .add_startup_system(spawn_stars)
+        .add_system(follow_camera);
+    }
+
+}
+
+#[derive(Component)]
+pub struct Starfield {
+    /// Number of stars drawn and the faintest magnitude among them
+    pub count: usize,
+    pub faintest: f32,
+}
+
+pub struct Star {
+    pub name: String,
+    /// Right ascension (ICRF) in degrees
+    pub ra: f32,
+    pub dec: f32,
+    /// Visual magnitude
+    pub magnitude: f32,
+    /// B-V color index
+    pub color_index: f32,
+}
+
+/// Discs with a soft edge and the color and brightness of their vertices
+#[derive(AsBindGroup, TypeUuid, Debug, Clone)]
+#[uuid = "5f1c7e2a-3b8d-4e6f-a1c2-7d9e0b4f6a83"]
+pub struct StarMaterial {}
+
+impl Material for StarMaterial {
+
+    fn fragment_shader() -> ShaderRef {
+        "shaders/stars.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayout,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        descriptor.primitive.cull_mode = None;
+        Ok(())
+    }
+
+}
+
+/// Reads stars from a CSV file with the HYG columns proper, ra (hours), dec, mag and ci, brighter than `limit`
+pub fn parse_stars(text: &str, limit: f32) -> Vec<Star> {
+    let mut lines = text.lines();
+    let header: Vec<&str> = match lines.next() {
+        Some(header) => header.split(',').map(|column| column.trim().trim_matches('"')).collect(),
+        None => return Vec::new()
+    };
+    let column = |name: &str| header.iter().position(|column| *column == name);
+    let (name, ra, dec, mag, ci) = match (column("proper"), column("ra"), column("dec"), column("mag"), column("ci")) {
+        (name, Some(ra), Some(dec), Some(mag), ci) => (name, ra, dec, mag, ci),
+        _ => {
+            warn!("Star catalog is missing the ra, dec or mag column");
+            return Vec::new();
+        }
+    };
+    lines.filter_map(|line| {
+        let fields: Vec<&str> = line.split(',').map(|field| field.trim().trim_matches('"')).collect();
+        let number = |index: usize| fields.get(index)?.parse::<f32>().ok();
+        let name = name.and_then(|index| fields.get(index)).map_or(String::new(), |name| name.to_string());
+        let magnitude = number(mag)?;
+        // HYG lists the Sun as its first entry
+        if magnitude > limit || name == "Sol" {
+            return None;
+        }
+        Some(Star {
+            name,
+            ra: number(ra)? * 15.0,
+            dec: number(dec)?,
+            magnitude,
+            color_index: ci.and_then(number).unwrap_or(0.6)
+        })
+    }).collect()
+}
+
+/// Approximate color of a star from its B-V index through its temperature (Ballesteros) and a fit of the blackbody colors
+pub fn star_color(color_index: f32) -> Color {
+    let temperature = 4600.0 * (1.0 / (0.92 * color_index + 1.7) + 1.0 / (0.92 * color_index + 0.62));
+    let t = temperature / 100.0;
+    let red = if t <= 66.0 { 255.0 } else { 329.698_73 * (t - 60.0).powf(-0.133_204_76) };
+    let green = if t <= 66.0 { 99.470_8 * t.ln() - 161.119_57 } else { 288.122_17 * (t - 60.0).powf(-0.075_514_85) };
+    let blue = if t >= 66.0 { 255.0 } else if t <= 19.0 { 0.0 } else { 138.517_73 * (t - 10.0).ln() - 305.044_8 };
+    Color::rgb(
+        (red / 255.0).clamp(0.0, 1.0),
+        (green / 255.0).clamp(0.0, 1.0),
+        (blue / 255.0).clamp(0.0, 1.0)
+    )
+}
+
+/// One quad per star on a sphere around the origin, facing its center
+pub fn star_mesh(stars: &[Star], radius: f32) -> Mesh {
+    let mut positions = Vec::with_capacity(4 * stars.len());
+    let mut colors = Vec::with_capacity(4 * stars.len());
+    let mut uvs = Vec::with_capacity(4 * stars.len());
+    let mut indices = Vec::with_capacity(6 * stars.len());
+    for star in stars {
+        let direction = radec_to_ecliptic(star.ra, star.dec);
+        let east = direction.any_orthonormal_vector();
+        let north = direction.cross(east);
+        // brighter stars are both larger and more opaque
+        let size = radius * STAR_SIZE * 10f32.powf(-0.1 * star.magnitude).clamp(0.4, 2.0);
+        let brightness = 10f32.powf(-0.2 * (star.magnitude - 1.0)).clamp(0.1, 1.0);
+        let color = star_color(star.color_index).as_rgba_f32();
+        let start = positions.len() as u32;
+        for (u, v) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+            let corner = direction * radius + (east * (u * 2.0 - 1.0) + north * (v * 2.0 - 1.0)) * size;
+            positions.push(corner.to_array());
+            colors.push([color[0], color[1], color[2], brightness]);
+            uvs.push([u, v]);
+        }
+        indices.extend_from_slice(&[start, start + 1, start + 2, start, start + 2, start + 3]);
+    }
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, positions.iter().map(|position| (-Vec3::from_array(*position).normalize()).to_array()).collect::<Vec<_>>());
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+fn spawn_stars(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StarMaterial>>
+) {
+    let stars = match std::fs::read_to_string(HYG_FILE).or_else(|_| {
+        warn!("{} is missing, only the bright stars of {} are drawn", HYG_FILE, BRIGHT_STARS_FILE);
+        std::fs::read_to_string(BRIGHT_STARS_FILE)
+    }) {
+        Ok(text) => parse_stars(&text, MAGNITUDE_LIMIT),
+        Err(error) => {
+            warn!("Could not read a star catalog: {}", error);
+            return;
+        }
+    };
+    commands.spawn(MaterialMeshBundle {
+        mesh: meshes.add(star_mesh(&stars, SPHERE_RADIUS)),
+        material: materials.add(StarMaterial {}),
+        ..default()
+    })
+    .insert(NotShadowCaster)
+    .insert(NoFrustumCulling)
+    .insert(Starfield {
+        count: stars.len(),
+        faintest: stars.iter().map(|star| star.magnitude).fold(f32::NEG_INFINITY, f32::max)
+    });
+}
+
+/// Keeps the stars centered on the camera so they show no parallax
+fn follow_camera(
+    camera: Query<&Transform, With<PanOrbitCamera>>,
+    mut starfield: Query<&mut Transform, (With<Starfield>, Without<PanOrbitCamera>)>
+) {
+    if let (Ok(camera), Ok(mut starfield)) = (camera.get_single(), starfield.get_single_mut()) {
+        starfield.translation = camera.translation;
+    }
+}
@@ -0,0 +1,132 @@
+use bevy::prelude::*;
+
+use crate::{astro::radec_to_ecliptic, body::Sun, jwst::{orbit_around_l2, JWST}, SimState};
+
+const TARGET_FILE: &str = "assets/targets/jwst_targets.csv";
+/// Range of the angle between the Sun and a target JWST can point at, in degrees
+pub const FIELD_OF_REGARD: (f32, f32) = (85.0, 135.0);
+/// Days covered by the visibility timeline
+pub const TIMELINE_DAYS: usize = 366;
+const YEAR: f32 = 365.25;
+
+pub struct TargetPlugin;
+
+impl Plugin for TargetPlugin {
+
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<Targets>()
+        .add_startup_system(load_targets)
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(update_field_of_regard.after(orbit_around_l2)));
+    }
+
+}
+
+/// Fixed observation target
+pub struct Target {
+    pub name: String,
+    /// Right ascension and declination (ICRF) in degrees
+    pub ra: f32,
+    pub dec: f32,
+    /// Unit vector in the ecliptic frame
+    pub direction: Vec3,
+    /// Current angle between the Sun and the target as seen from JWST in degrees
+    pub elongation: f32,
+    /// Whether the target is in the field of regard on each of the next [TIMELINE_DAYS] days
+    pub timeline: Vec<bool>,
+}
+
+impl Target {
+
+    pub fn new(name: String, ra: f32, dec: f32) -> Self {
+        Self {
+            name,
+            ra,
+            dec,
+            direction: radec_to_ecliptic(ra, dec),
+            elongation: 0.0,
+            timeline: Vec::new()
+        }
+    }
+
+    pub fn observable(&self) -> bool {
+        in_field_of_regard(self.elongation)
+    }
+
+    /// First and last day (from today) of each stretch of the timeline the target can be observed
+    pub fn windows(&self) -> Vec<(usize, usize)> {
+        runs(&self.timeline)
+    }
+
+}
+
+/// First and last index of each run of `true` in a timeline
+pub fn runs(timeline: &[bool]) -> Vec<(usize, usize)> {
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for (index, set) in timeline.iter().enumerate() {
+        if !set {
+            continue;
+        }
+        match runs.last_mut() {
+            Some(run) if run.1 + 1 == index => run.1 = index,
+            _ => runs.push((index, index))
+        }
+    }
+    runs
+}
+
+/// Targets loaded from the target list, the timeline window is toggled in the UI
+#[derive(Resource, Default)]
+pub struct Targets {
+    pub show: bool,
+    pub targets: Vec<Target>,
+}
+
+pub fn in_field_of_regard(elongation: f32) -> bool {
+    (FIELD_OF_REGARD.0..=FIELD_OF_REGARD.1).contains(&elongation)
+}
+
+/// Reads targets from a CSV file with the columns name, ra and dec (degrees)
+pub fn parse_targets(text: &str) -> Vec<Target> {
+    text.lines().skip(1).filter_map(|line| {
+        // names may contain commas, the coordinates are the last two fields
+        let mut fields = line.rsplitn(3, ',');
+        let dec = fields.next()?.trim().parse::<f32>().ok()?;
+        let ra = fields.next()?.trim().parse::<f32>().ok()?;
+        let name = fields.next()?.trim().trim_matches('"').to_string();
+        Some(Target::new(name, ra, dec))
+    }).collect()
+}
+
+fn load_targets(mut targets: ResMut<Targets>) {
+    match std::fs::read_to_string(TARGET_FILE) {
+        Ok(text) => targets.targets = parse_targets(&text),
+        Err(error) => warn!("Could not read {}: {}", TARGET_FILE, error)
+    }
+}
+
+/// Sun angles of all targets from JWST now and for the next year. JWST stays close to L2, so the direction
+/// to the Sun turns with Earth's mean motion around the ecliptic pole.
+fn update_field_of_regard(
+    mut targets: ResMut<Targets>,
+    jwst: Query<&Transform, With<JWST>>,
+    sun: Query<&Transform, (With<Sun>, Without<JWST>)>
+) {
+    let (jwst, sun) = match (jwst.get_single(), sun.get_single()) {
+        (Ok(jwst), Ok(sun)) => (jwst, sun),
+        _ => return
+    };
+    let to_sun = (sun.translation - jwst.translation).normalize_or_zero();
+    if to_sun == Vec3::ZERO {
+        return;
+    }
+    let future: Vec<Vec3> = (0..TIMELINE_DAYS)
+        .map(|day| Quat::from_rotation_z(std::f32::consts::TAU * day as f32 / YEAR) * to_sun)
+        .collect();
+    for target in targets.targets.iter_mut() {
+        target.elongation = target.direction.angle_between(to_sun).to_degrees();
+        target.timeline = future.iter()
+            .map(|to_sun| in_field_of_regard(target.direction.angle_between(*to_sun).to_degrees()))
+            .collect();
+    }
+}
@@ -0,0 +1,169 @@
+use bevy::{prelude::*, core_pipeline::clear_color::ClearColorConfig, render::{camera::RenderTarget, render_resource::{Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages}}};
+use bevy_mod_picking::Selection;
+
+use crate::{attitude::{Attitude, update_attitude}, jwst::{orbit_around_l2, JWST}, targets::Targets, SimState};
+
+/// Height of the rendered telescope image in pixels, the width follows the instrument's aspect ratio
+const IMAGE_HEIGHT: u32 = 256;
+/// Distance of the camera in front of the JWST model so the model stays behind the near plane
+const CAMERA_OFFSET: f32 = 0.003;
+
+pub struct TelescopePlugin;
+
+impl Plugin for TelescopePlugin {
+
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<Telescope>()
+        .add_startup_system(spawn_telescope_camera)
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(resolve_pointing.after(orbit_around_l2)))
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(point_telescope.after(update_attitude)));
+    }
+
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Instrument {
+    #[default]
+    NIRCam,
+    MIRI,
+}
+
+impl Instrument {
+
+    /// Width and height of the imaging field of view in arcminutes. NIRCam images two adjacent 2.2' modules,
+    /// the MIRI imager covers 74" x 113"
+    pub fn field_of_view(&self) -> (f32, f32) {
+        match self {
+            Instrument::NIRCam => (4.4, 2.2),
+            Instrument::MIRI => (113.0 / 60.0, 74.0 / 60.0)
+        }
+    }
+
+}
+
+/// Second camera at JWST, rendered into [Telescope::image] and shown in the telescope window
+#[derive(Resource, Default)]
+pub struct Telescope {
+    pub enabled: bool,
+    pub instrument: Instrument,
+    /// Name of the target from the target list or of the body the telescope points at
+    pub pointing: Option<String>,
+    /// Unit vector from JWST towards the pointing in the ecliptic frame
+    pub direction: Option<Vec3>,
+    pub image: Handle<Image>,
+}
+
+#[derive(Component)]
+pub struct TelescopeCamera;
+
+fn telescope_image(instrument: Instrument) -> Image {
+    let (width, height) = instrument.field_of_view();
+    let size = Extent3d {
+        width: (IMAGE_HEIGHT as f32 * width / height).round() as u32,
+        height: IMAGE_HEIGHT,
+        ..default()
+    };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some("telescope_image"),
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | TextureUsages::RENDER_ATTACHMENT,
+        },
+        ..default()
+    };
+    image.resize(size);
+    image
+}
+
+fn spawn_telescope_camera(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut telescope: ResMut<Telescope>
+) {
+    telescope.image = images.add(telescope_image(telescope.instrument));
+    commands.spawn((
+        Camera3dBundle {
+            projection: Projection::Perspective(PerspectiveProjection {
+                fov: (telescope.instrument.field_of_view().1 / 60.0).to_radians(),
+                near: 0.0001,
+                ..default()
+            }),
+            camera_3d: Camera3d {
+                clear_color: ClearColorConfig::Custom(Color::BLACK),
+                ..default()
+            },
+            camera: Camera {
+                // rendered before the main camera and only while the window is open
+                priority: -1,
+                is_active: false,
+                target: RenderTarget::Image(telescope.image.clone()),
+                ..default()
+            },
+            ..default()
+        },
+        TelescopeCamera,
+        Name::new("Telescope camera"),
+    ));
+}
+
+/// Looks up the direction of the selected target or body, the first target is selected by default
+pub fn resolve_pointing(
+    mut telescope: ResMut<Telescope>,
+    targets: Res<Targets>,
+    jwst: Query<&Transform, With<JWST>>,
+    bodies: Query<(&Name, &Transform), (With<Selection>, Without<JWST>)>
+) {
+    let jwst = match jwst.get_single() {
+        Ok(jwst) => jwst.translation,
+        Err(_) => return
+    };
+    if telescope.pointing.is_none() {
+        telescope.pointing = targets.targets.first().map(|target| target.name.clone());
+    }
+    telescope.direction = telescope.pointing.as_ref().and_then(|name| {
+        targets.targets.iter()
+            .find(|target| &target.name == name)
+            .map(|target| target.direction)
+            .or_else(|| bodies.iter().find(|(body, _)| body.as_str() == name).and_then(|(_, body)| (body.translation - jwst).try_normalize()))
+    });
+}
+
+/// Moves the telescope camera to JWST and turns it with the spacecraft's attitude
+fn point_telescope(
+    telescope: Res<Telescope>,
+    mut images: ResMut<Assets<Image>>,
+    jwst: Query<(&Transform, &Attitude), (With<JWST>, Without<TelescopeCamera>)>,
+    mut camera: Query<(&mut Camera, &mut Transform, &mut Projection), With<TelescopeCamera>>
+) {
+    let (mut camera, mut transform, mut projection) = match camera.get_single_mut() {
+        Ok(camera) => camera,
+        Err(_) => return
+    };
+    camera.is_active = telescope.enabled;
+    if !telescope.enabled {
+        return;
+    }
+    let (width, height) = telescope.instrument.field_of_view();
+    if let Projection::Perspective(perspective) = projection.as_mut() {
+        let fov = (height / 60.0).to_radians();
+        if perspective.fov != fov {
+            perspective.fov = fov;
+            // the camera picks up the new aspect ratio from the resized image
+            let width = (IMAGE_HEIGHT as f32 * width / height).round() as u32;
+            if let Some(image) = images.get_mut(&telescope.image) {
+                image.resize(Extent3d { width, height: IMAGE_HEIGHT, ..default() });
+            }
+        }
+    }
+    if let Ok((jwst, attitude)) = jwst.get_single() {
+        if attitude.boresight != Vec3::ZERO {
+            *transform = Transform::from_translation(jwst.translation + attitude.boresight * CAMERA_OFFSET)
+                .looking_at(jwst.translation + attitude.boresight, attitude.up);
+        }
+    }
+}
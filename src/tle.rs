@@ -0,0 +1,98 @@
+use bevy::{prelude::*, math::DVec3};
+use chrono::NaiveDateTime;
+
+use crate::{astro::teme_to_ecliptic, drag::Drag, satellites::LocalState};
+
+/// Two-line element sets loaded when the simulation starts
+pub const TLE_FILE: &str = "assets/tle/satellites.txt";
+/// Reference density of the B* drag term in kg/m² per Earth radius
+const BSTAR_DENSITY: f64 = 0.156_966_15;
+
+/// Reads all element sets from a file in 2-line or 3-line format, lines starting with # are comments
+pub fn load_tles(path: &str) -> Vec<sgp4::Elements> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(error) => {
+            warn!("Could not read {}: {}", path, error);
+            return Vec::new();
+        }
+    };
+    parse_tles(&text)
+}
+
+pub fn parse_tles(text: &str) -> Vec<sgp4::Elements> {
+    let lines: Vec<&str> = text.lines()
+        .map(|line| line.trim_end())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    let mut elements = Vec::new();
+    let mut name: Option<String> = None;
+    let mut i = 0;
+    while i < lines.len() {
+        if lines[i].starts_with("1 ") && i + 1 < lines.len() && lines[i + 1].starts_with("2 ") {
+            match sgp4::Elements::from_tle(name.take(), lines[i].as_bytes(), lines[i + 1].as_bytes()) {
+                Ok(set) => elements.push(set),
+                Err(error) => warn!("Invalid TLE '{}': {}", lines[i], error)
+            }
+            i += 2;
+        } else {
+            name = Some(lines[i].trim().to_string());
+            i += 1;
+        }
+    }
+    elements
+}
+
+/// Name of a satellite, falling back to its catalog number
+pub fn tle_name(elements: &sgp4::Elements) -> String {
+    elements.object_name.clone().unwrap_or_else(|| elements.norad_id.to_string())
+}
+
+/// Drag model matching the B* term of the element set, if it has one
+pub fn tle_drag(elements: &sgp4::Elements) -> Option<Drag> {
+    if elements.drag_term > 0.0 {
+        Some(Drag {
+            ballistic_coefficient: (BSTAR_DENSITY / (2.0 * elements.drag_term)) as f32
+        })
+    } else {
+        None
+    }
+}
+
+/// SGP4 propagator of a satellite loaded from a TLE
+#[derive(Component)]
+pub struct Sgp4Orbit {
+    constants: sgp4::Constants,
+    epoch: NaiveDateTime,
+}
+
+impl Sgp4Orbit {
+
+    pub fn new(elements: &sgp4::Elements) -> Option<Self> {
+        Some(Self {
+            constants: sgp4::Constants::from_elements(elements).ok()?,
+            epoch: elements.datetime
+        })
+    }
+
+    /// Geocentric position (km) and velocity (km/s) in the ecliptic frame at `date`
+    pub fn state(&self, date: NaiveDateTime) -> Option<(DVec3, DVec3)> {
+        let minutes = (date - self.epoch).num_milliseconds() as f64 / 60_000.0;
+        let prediction = self.constants.propagate(sgp4::MinutesSinceEpoch(minutes)).ok()?;
+        Some((
+            teme_to_ecliptic(DVec3::from_array(prediction.position), date),
+            teme_to_ecliptic(DVec3::from_array(prediction.velocity), date)
+        ))
+    }
+
+    /// Initial state for the integration around Earth at `date`
+    pub fn local_state(&self, date: NaiveDateTime) -> Option<LocalState> {
+        let (position, velocity) = self.state(date)?;
+        Some(LocalState {
+            parent: "Earth".to_string(),
+            position,
+            velocity
+        })
+    }
+
+}
@@ -1,14 +1,24 @@
-use bevy::{prelude::{Plugin, App, Name, Query, ResMut, Mut, Entity, Commands, DespawnRecursiveExt, Transform, Vec3, Res, Resource, IntoSystemDescriptor, ParamSet, Visibility, Without, With, MaterialMeshBundle, Camera, SystemSet, PointLight, Input, KeyCode, State}, time::Time, diagnostic::{Diagnostic, Diagnostics, FrameTimeDiagnosticsPlugin}, render::FrameCountPlugin, window::{Windows, PresentMode}};
+use std::marker::PhantomData;
+
+use bevy::{ecs::system::SystemParam, prelude::{Plugin, App, Name, Query, ResMut, Mut, Entity, Commands, DespawnRecursiveExt, Transform, Vec3, Res, Resource, IntoSystemDescriptor, ParamSet, Visibility, Without, With, MaterialMeshBundle, Camera, SystemSet, PointLight, Input, KeyCode, State, EventReader}, time::Time, diagnostic::{Diagnostic, Diagnostics, FrameTimeDiagnosticsPlugin}, render::FrameCountPlugin, window::{Windows, PresentMode}};
 use bevy_egui::*;
 use bevy_inspector_egui::{egui::{TextEdit, RichText}, Inspectable, RegisterInspectable};
 use bevy_mod_picking::Selection;
 use chrono::{NaiveDate, Days};
 
-use crate::{body::{Mass, Velocity, Acceleration, update_bodies, Pause}, input::BlockInputPlugin, lagrange::LagrangePoint, skybox::{CubemapMaterial, Skybox}, speed::Speed, fps::Fps, SimState, camera::LockSun};
+use crate::{body::{Mass, Velocity, Acceleration, update_bodies, Pause, CloseEncounters}, input::BlockInputPlugin, lagrange::LagrangePoint, skybox::{CubemapMaterial, Skybox, SkyboxOrientation, SkyFrame}, speed::Speed, fps::Fps, SimState, camera::{LockSun, PanOrbitCamera}, frame::RotatingFrame, lines::ShowTrails, jacobi::{JacobiConstant, ZeroVelocityCurves}, cr3bp, manifold::Manifolds, collision::{CollisionEvent, CollisionOutcome, Collisions}, relativity::{Perihelion, Relativity, RESOLVABLE_FACTOR}, oblateness::ZonalHarmonics, radiation::SolarRadiation, jwst::ScriptedOrbit, drag::{AtmosphericDrag, Decay}, satellites::Satellites, minor_bodies::{MinorBodies, MinorBody}, stars::Starfield, constellations::Constellations, grids::Grids, targets::{Targets, FIELD_OF_REGARD, TIMELINE_DAYS}, telescope::{Telescope, Instrument}, attitude::{Attitude, ROLL_LIMIT}, eclipse::{EclipseEvent, Eclipses, Shadow}, eclipse_search::EclipseSearch, ground_stations::{GroundStations, SAMPLES_PER_HOUR, TIMELINE_HOURS}, astro::sim_date};
 
 #[derive(Resource, Inspectable, Default)]
 pub struct SimTime(pub f32);
 
+/// Messages about impacts reported by [CollisionEvent]s
+#[derive(Resource, Default)]
+pub struct ImpactLog(pub Vec<String>);
+
+/// Messages about bodies entering and leaving shadows reported by [EclipseEvent]s
+#[derive(Resource, Default)]
+pub struct EclipseLog(pub Vec<String>);
+
 #[derive(Resource, Inspectable, Default)]
 pub struct Light {
     
@@ -24,10 +34,18 @@ impl Plugin for UIPlugin {
        // .add_plugin(EguiPlugin)
         .register_inspectable::<SimTime>()
         .init_resource::<SimTime>()
+        .init_resource::<ImpactLog>()
+        .init_resource::<EclipseLog>()
         .add_plugin(BlockInputPlugin)
         .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(system_ui.after(time_ui)))
         .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(body_ui.after(update_bodies)))
-        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(time_ui.after(body_ui)));
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(time_ui.after(body_ui)))
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(impact_ui.after(system_ui)))
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(eclipse_ui.after(system_ui)))
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(eclipse_search_ui.after(system_ui)))
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(targets_ui.after(system_ui)))
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(telescope_ui.after(system_ui)))
+        .add_system_set(SystemSet::on_update(SimState::Simulation).with_system(ground_stations_ui.after(system_ui)));
     }
 }
 
@@ -93,24 +111,66 @@ pub fn time_ui(
     });
 }
 
+/// Settings of the physics and the tools shown in the options of [system_ui]
+#[derive(SystemParam)]
+pub struct SimulationOptions<'w, 's> {
+    frame: ResMut<'w, RotatingFrame>,
+    show_trails: ResMut<'w, ShowTrails>,
+    zero_velocity: ResMut<'w, ZeroVelocityCurves>,
+    manifolds: ResMut<'w, Manifolds>,
+    collisions: ResMut<'w, Collisions>,
+    encounters: ResMut<'w, CloseEncounters>,
+    relativity: ResMut<'w, Relativity>,
+    harmonics: ResMut<'w, ZonalHarmonics>,
+    radiation: ResMut<'w, SolarRadiation>,
+    drag: ResMut<'w, AtmosphericDrag>,
+    scripted_orbit: ResMut<'w, ScriptedOrbit>,
+    satellites: ResMut<'w, Satellites>,
+    minor_bodies: ResMut<'w, MinorBodies>,
+    eclipses: ResMut<'w, Eclipses>,
+    eclipse_search: ResMut<'w, EclipseSearch>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+/// Background, overlays and JWST views shown in the options of [system_ui]
+#[derive(SystemParam)]
+pub struct ViewOptions<'w, 's> {
+    skybox: Query<'w, 's, (&'static mut Visibility, &'static Skybox, Without<LagrangePoint>, Without<Selection>, Without<Name>)>,
+    starfield: Query<'w, 's, (&'static mut Visibility, &'static Starfield), (Without<Skybox>, Without<Name>)>,
+    skybox_orientation: ResMut<'w, SkyboxOrientation>,
+    constellations: ResMut<'w, Constellations>,
+    grids: ResMut<'w, Grids>,
+    targets: ResMut<'w, Targets>,
+    telescope: ResMut<'w, Telescope>,
+    ground_stations: ResMut<'w, GroundStations>,
+}
+
 pub fn system_ui(
     mut egui_context: ResMut<EguiContext>,
-    mut body_query: Query<(&Name, &mut Selection, &mut Visibility, Without<LagrangePoint>)>,
+    mut body_query: Query<(&Name, &mut Selection, &mut Visibility, Without<LagrangePoint>, Without<MinorBody>)>,
     mut lagrange_point_query: Query<(&Name, &mut Selection, &mut Visibility, With<LagrangePoint>)>,
-    mut skybox: Query<(&mut Visibility, &Skybox, Without<LagrangePoint>, Without<Selection>, Without<Name>)>,
-    mut camera: Query<&mut Camera>,
+    mut camera: Query<&mut Camera, With<PanOrbitCamera>>,
     mut light: Query<&mut PointLight>,
+    simulation: SimulationOptions,
+    view: ViewOptions,
     mut state: ResMut<State<SimState>>
 ) {
+    let SimulationOptions {
+        mut frame, mut show_trails, mut zero_velocity, mut manifolds, mut collisions, mut encounters, mut relativity, mut harmonics,
+        mut radiation, mut drag, mut scripted_orbit, mut satellites, mut minor_bodies, mut eclipses, mut eclipse_search, ..
+    } = simulation;
+    let ViewOptions { mut skybox, mut starfield, mut skybox_orientation, mut constellations, mut grids, mut targets, mut telescope, mut ground_stations } = view;
     let mut points: Vec<(&Name, Mut<Selection>)> = Vec::new();
     let mut selected_body: Option<&str> = None;
+    let mut body_names: Vec<String> = Vec::new();
     
     egui::SidePanel::left("system_panel")
     .default_width(400.0)
     .resizable(true)
     .show(egui_context.ctx_mut(), | ui| {
         ui.heading("Bodies");
-        for (name, mut selected, mut visibility, _) in body_query.iter_mut() {
+        for (name, mut selected, mut visibility, _, _) in body_query.iter_mut() {
             ui.horizontal(|ui| {
                 ui.checkbox(&mut visibility.is_visible, "");
                 if ui.button(name.as_str()).clicked() {
@@ -118,6 +178,7 @@ pub fn system_ui(
                     selected_body = Some(name.as_str());
                 }
             });
+            body_names.push(name.to_string());
             points.push((name, selected));
         }
         ui.heading("Lagrange Points");
@@ -133,14 +194,142 @@ pub fn system_ui(
         }
         ui.heading("Options");
         if let Ok(mut visible) = skybox.get_single_mut() {
-           ui.checkbox(&mut visible.0.is_visible, "Milky Way background"); 
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut visible.0.is_visible, "Milky Way background");
+                egui::ComboBox::from_id_source("skybox_frame")
+                .selected_text(format!("{:?}", skybox_orientation.frame))
+                .show_ui(ui, |ui| {
+                    for frame in [SkyFrame::Ecliptic, SkyFrame::Equatorial, SkyFrame::Galactic] {
+                        ui.selectable_value(&mut skybox_orientation.frame, frame, format!("{:?}", frame));
+                    }
+                });
+            });
+        }
+        if let Ok((mut visible, stars)) = starfield.get_single_mut() {
+            ui.checkbox(&mut visible.is_visible, format!("Stars ({} to mag {:.1})", stars.count, stars.faintest));
         }
+        ui.horizontal(|ui| {
+            let label = format!("Constellations ({} of 88)", constellations.count);
+            ui.checkbox(&mut constellations.visible, label);
+            ui.add_enabled(constellations.visible, egui::Checkbox::new(&mut constellations.labels, "Names"));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Grids");
+            ui.checkbox(&mut grids.ecliptic, "Ecliptic");
+            ui.checkbox(&mut grids.equatorial, "Equatorial");
+            ui.checkbox(&mut grids.galactic, "Galactic");
+            ui.checkbox(&mut grids.plane, "Plane");
+            ui.checkbox(&mut grids.labels, "Labels");
+        });
         if let Ok(mut camera) = camera.get_single_mut() {
             ui.checkbox(&mut camera.hdr, "HDR/Bloom");
         }
         if let Ok(mut light) = light.get_single_mut() {
             ui.checkbox(&mut light.shadows_enabled, "Shadows");
         }
+        ui.checkbox(&mut show_trails.0, "Orbit trails");
+        ui.checkbox(&mut frame.enabled, "Rotating frame");
+        if frame.enabled {
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source("frame_primary")
+                .selected_text(frame.primary.clone())
+                .show_ui(ui, |ui| {
+                    for name in body_names.iter() {
+                        ui.selectable_value(&mut frame.primary, name.clone(), name.as_str());
+                    }
+                });
+                egui::ComboBox::from_id_source("frame_secondary")
+                .selected_text(frame.secondary.clone())
+                .show_ui(ui, |ui| {
+                    for name in body_names.iter() {
+                        ui.selectable_value(&mut frame.secondary, name.clone(), name.as_str());
+                    }
+                });
+            });
+        }
+        ui.checkbox(&mut zero_velocity.enabled, "Zero-velocity curves");
+        if zero_velocity.enabled {
+            ui.checkbox(&mut zero_velocity.follow_spacecraft, "Use C of spacecraft");
+            ui.horizontal(|ui| {
+                ui.label("C");
+                ui.add_enabled(!zero_velocity.follow_spacecraft, egui::DragValue::new(&mut zero_velocity.constant).speed(0.00001).max_decimals(6));
+                let mu = frame.mu as f64;
+                if ui.small_button("L1").clicked() {
+                    zero_velocity.follow_spacecraft = false;
+                    zero_velocity.constant = cr3bp::jacobi_constant(mu, bevy::math::DVec3::new(cr3bp::collinear_point(mu, 1), 0.0, 0.0), bevy::math::DVec3::ZERO);
+                }
+                if ui.small_button("L2").clicked() {
+                    zero_velocity.follow_spacecraft = false;
+                    zero_velocity.constant = cr3bp::jacobi_constant(mu, bevy::math::DVec3::new(cr3bp::collinear_point(mu, 2), 0.0, 0.0), bevy::math::DVec3::ZERO);
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut collisions.enabled, "Collisions");
+            egui::ComboBox::from_id_source("collision_outcome")
+            .selected_text(format!("{:?}", collisions.outcome))
+            .show_ui(ui, |ui| {
+                for outcome in [CollisionOutcome::Merge, CollisionOutcome::Bounce, CollisionOutcome::Despawn] {
+                    ui.selectable_value(&mut collisions.outcome, outcome, format!("{:?}", outcome));
+                }
+            });
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut eclipses.enabled, "Eclipse events");
+            ui.checkbox(&mut eclipse_search.show, "Eclipse search");
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut encounters.softening, "Softening");
+            ui.add_enabled(encounters.softening, egui::DragValue::new(&mut encounters.softening_length).speed(0.0001).clamp_range(0.0..=1.0).suffix(" u"));
+        });
+        ui.checkbox(&mut encounters.refine_steps, "Refine close encounters");
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut relativity.enabled, "General relativity");
+            ui.add_enabled(relativity.enabled, egui::DragValue::new(&mut relativity.factor).speed(10.0).clamp_range(1.0..=1_000_000.0).prefix("x"));
+        });
+        if relativity.enabled && relativity.factor < RESOLVABLE_FACTOR {
+            ui.label(RichText::new(format!("The real effect is below f32 precision, the measured precession is integrator noise until the factor is at least x{:.0}", RESOLVABLE_FACTOR)).color(egui::Color32::LIGHT_RED));
+        }
+        ui.checkbox(&mut harmonics.enabled, "Oblateness (J2)");
+        ui.checkbox(&mut radiation.enabled, "Solar radiation pressure");
+        ui.checkbox(&mut drag.enabled, "Atmospheric drag");
+        ui.checkbox(&mut scripted_orbit.0, "Scripted JWST orbit");
+        if radiation.enabled && scripted_orbit.0 {
+            // the scripted orbit moves JWST without forces
+            ui.label(RichText::new("Radiation pressure does not act on JWST while it follows the scripted orbit").small());
+        }
+        ui.checkbox(&mut targets.show, "JWST targets");
+        ui.checkbox(&mut telescope.enabled, "Telescope view");
+        ui.checkbox(&mut ground_stations.show, "DSN ground stations");
+        ui.checkbox(&mut satellites.enabled, "Satellites (on reset)");
+        ui.checkbox(&mut satellites.exaggerate, "Exaggerate satellite orbits");
+        ui.checkbox(&mut satellites.sgp4, "SGP4 for TLE satellites");
+        ui.checkbox(&mut minor_bodies.visible, format!("Minor bodies ({})", minor_bodies.count));
+        ui.checkbox(&mut manifolds.enabled, "Invariant manifolds");
+        if manifolds.enabled {
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut manifolds.point, 1, "L1");
+                ui.radio_value(&mut manifolds.point, 2, "L2");
+                ui.checkbox(&mut manifolds.stable, "Stable");
+                ui.checkbox(&mut manifolds.unstable, "Unstable");
+            });
+            ui.horizontal(|ui| {
+                ui.label("Amplitude");
+                ui.add(egui::DragValue::new(&mut manifolds.amplitude_km).speed(1000.0).clamp_range(1000.0..=800_000.0).suffix(" km"));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Trajectories");
+                ui.add(egui::DragValue::new(&mut manifolds.trajectories).clamp_range(1..=200));
+                ui.label("Days");
+                ui.add(egui::DragValue::new(&mut manifolds.duration_days).speed(5.0).clamp_range(10.0..=1000.0));
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Compute").clicked() {
+                    manifolds.compute = true;
+                }
+                ui.label(manifolds.status.as_str());
+            });
+        }
         ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
             if ui.button("Back to Menu").clicked() {
                 let _ = state.set(SimState::ExitToMainMenu);
@@ -159,10 +348,10 @@ pub fn system_ui(
 fn body_ui(
     mut egui_context: ResMut<EguiContext>,
     mut commands: Commands,
-    mut query: Query<(&Name, &Selection, Entity, &Transform, &Velocity, &mut Mass)>
+    mut query: Query<(&Name, &Selection, Entity, &Transform, &Velocity, &mut Mass, Option<&JacobiConstant>, Option<&mut Perihelion>, Option<&mut Decay>, Option<&mut Attitude>)>
 ) {
     let sun_pos = Vec3::splat(0.0);
-    for (name, selection, entity, transform, velocity, mut mass) in query.iter_mut() {
+    for (name, selection, entity, transform, velocity, mut mass, jacobi, perihelion, decay, attitude) in query.iter_mut() {
         if selection.selected() {
             egui::SidePanel::right("body_panel")
             .max_width(250.0)
@@ -206,6 +395,53 @@ fn body_ui(
                 let distance_in_au = transform.translation.distance(sun_pos) / 10.0;
                 ui.label(format!("{} km", (distance_in_au * 1.496e+8) as f64));
                 ui.label(format!("{:.3} au", distance_in_au));
+                if let Some(jacobi) = jacobi {
+                    ui.label(RichText::new("Jacobi constant").size(16.0).underline());
+                    ui.label(format!("{:.6}", jacobi.0));
+                }
+                if let Some(mut perihelion) = perihelion {
+                    ui.label(RichText::new("Perihelion precession").size(16.0).underline());
+                    ui.label(format!("e = {:.5}", perihelion.eccentricity));
+                    match perihelion.precession.rate {
+                        Some(rate) => {
+                            ui.label(format!("{:.1}″ / century", rate));
+                        },
+                        None => {
+                            ui.label("Measuring...");
+                        }
+                    }
+                    if ui.small_button("Restart").clicked() {
+                        perihelion.precession.restart();
+                    }
+                }
+                if let Some(mut decay) = decay {
+                    ui.label(RichText::new("Orbital decay").size(16.0).underline());
+                    match decay.mean_altitude {
+                        Some(altitude) => {
+                            ui.label(format!("Mean altitude: {:.1} km", altitude));
+                        },
+                        None => {
+                            ui.label("Not in orbit");
+                        }
+                    }
+                    if let Some(rate) = decay.semi_major_axis.rate {
+                        ui.label(format!("{:.3} km / day", rate));
+                    }
+                    if ui.small_button("Restart").clicked() {
+                        decay.semi_major_axis.restart();
+                    }
+                }
+                if let Some(mut attitude) = attitude {
+                    ui.label(RichText::new("Attitude").size(16.0).underline());
+                    ui.label(format!("Sun pitch: {:+.1}°", attitude.pitch()));
+                    ui.add(egui::Slider::new(&mut attitude.roll, -10.0..=10.0).text("Roll (°)"));
+                    if attitude.pitch_violation {
+                        ui.label(RichText::new("Target outside the sun pitch limits").color(egui::Color32::LIGHT_RED));
+                    }
+                    if attitude.roll_violation {
+                        ui.label(RichText::new(format!("Roll limited to ±{:.0}°", ROLL_LIMIT)).color(egui::Color32::LIGHT_RED));
+                    }
+                }
                 ui.with_layout(egui::Layout::bottom_up(egui::Align::Center), |ui| {
                     if ui.button("Delete").clicked() {
                         commands.entity(entity).despawn_recursive()
@@ -215,4 +451,270 @@ fn body_ui(
             
         }
     }
-}
\ No newline at end of file
+}
+
+pub fn impact_ui(
+    mut egui_context: ResMut<EguiContext>,
+    mut events: EventReader<CollisionEvent>,
+    mut log: ResMut<ImpactLog>
+) {
+    for event in events.iter() {
+        log.0.push(format!("{}: {} hit {} at {:.2} km/s ({:?})", sim_date(event.time).format("%d.%m.%Y %H:%M"), event.first, event.second, event.speed, event.outcome));
+    }
+    if log.0.is_empty() {
+        return;
+    }
+    let mut open = true;
+    egui::Window::new("Impacts")
+    .open(&mut open)
+    .default_width(300.0)
+    .show(egui_context.ctx_mut(), |ui| {
+        egui::ScrollArea::vertical().max_height(200.0).stick_to_bottom(true).show(ui, |ui| {
+            for line in log.0.iter() {
+                ui.label(line);
+            }
+        });
+    });
+    if !open {
+        log.0.clear();
+    }
+}
+
+pub fn eclipse_ui(
+    mut egui_context: ResMut<EguiContext>,
+    mut events: EventReader<EclipseEvent>,
+    mut log: ResMut<EclipseLog>
+) {
+    for event in events.iter() {
+        let shadow = match event.shadow {
+            Shadow::Umbra => "umbra",
+            Shadow::Antumbra => "antumbra",
+            _ => "penumbra"
+        };
+        let kind = match (event.body.as_str(), event.occulter.as_str()) {
+            ("Earth", "Moon") => " (solar eclipse)",
+            ("Moon", "Earth") => " (lunar eclipse)",
+            _ => ""
+        };
+        let action = if event.started { "enters" } else { "leaves" };
+        log.0.push(format!("{}: {} {} the {} of {}{}", sim_date(event.time).format("%d.%m.%Y %H:%M"), event.body, action, shadow, event.occulter, kind));
+    }
+    if log.0.is_empty() {
+        return;
+    }
+    let mut open = true;
+    egui::Window::new("Eclipses")
+    .open(&mut open)
+    .default_width(300.0)
+    .show(egui_context.ctx_mut(), |ui| {
+        egui::ScrollArea::vertical().max_height(200.0).stick_to_bottom(true).show(ui, |ui| {
+            for line in log.0.iter() {
+                ui.label(line);
+            }
+        });
+    });
+    if !open {
+        log.0.clear();
+    }
+}
+
+/// Upcoming eclipses seen from Earth, with buttons to move the simulation to the greatest eclipse
+pub fn eclipse_search_ui(
+    mut egui_context: ResMut<EguiContext>,
+    mut search: ResMut<EclipseSearch>
+) {
+    if !search.show {
+        return;
+    }
+    let mut open = true;
+    egui::Window::new("Eclipse search")
+    .open(&mut open)
+    .default_width(300.0)
+    .show(egui_context.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut search.days).clamp_range(1.0..=3650.0).speed(10.0).suffix(" days"));
+            if ui.button("Search").clicked() {
+                search.search = true;
+            }
+        });
+        let mut jump = None;
+        egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+            egui::Grid::new("eclipses").striped(true).show(ui, |ui| {
+                for (index, eclipse) in search.results.iter().enumerate() {
+                    ui.label(sim_date(eclipse.time).format("%d.%m.%Y %H:%M").to_string());
+                    ui.label(eclipse.kind.label());
+                    ui.label(format!("{}–{}", sim_date(eclipse.contacts.0).format("%H:%M"), sim_date(eclipse.contacts.1).format("%H:%M")))
+                    .on_hover_text("First and last contact with the penumbra");
+                    if ui.small_button("Jump").clicked() {
+                        jump = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+        if jump.is_some() {
+            search.jump = jump;
+        }
+    });
+    if !open {
+        search.show = false;
+    }
+}
+
+/// Targets in JWST's field of regard now and a timeline of their visibility over the next year
+pub fn targets_ui(
+    mut egui_context: ResMut<EguiContext>,
+    mut targets: ResMut<Targets>,
+    sim_time: Res<SimTime>
+) {
+    if !targets.show {
+        return;
+    }
+    let mut open = true;
+    egui::Window::new("JWST targets")
+    .open(&mut open)
+    .default_width(420.0)
+    .show(egui_context.ctx_mut(), |ui| {
+        ui.label(format!("Field of regard: {:.0}° to {:.0}° from the Sun", FIELD_OF_REGARD.0, FIELD_OF_REGARD.1));
+        egui::Grid::new("targets").striped(true).show(ui, |ui| {
+            ui.label("Target");
+            ui.label("Sun angle");
+            ui.label(format!("Next {} days", TIMELINE_DAYS));
+            ui.end_row();
+            for target in targets.targets.iter() {
+                let color = if target.observable() { egui::Color32::LIGHT_GREEN } else { egui::Color32::GRAY };
+                ui.label(RichText::new(&target.name).color(color));
+                ui.label(format!("{:.1}°", target.elongation));
+                let (rect, response) = ui.allocate_exact_size(egui::vec2(TIMELINE_DAYS as f32 * 0.6, 12.0), egui::Sense::hover());
+                let painter = ui.painter_at(rect);
+                painter.rect_filled(rect, 0.0, egui::Color32::from_gray(40));
+                let day_width = rect.width() / TIMELINE_DAYS as f32;
+                let windows = target.windows();
+                for (start, end) in windows.iter() {
+                    let window = egui::Rect::from_min_max(
+                        egui::pos2(rect.left() + *start as f32 * day_width, rect.top()),
+                        egui::pos2(rect.left() + (*end + 1) as f32 * day_width, rect.bottom())
+                    );
+                    painter.rect_filled(window, 0.0, egui::Color32::from_rgb(60, 160, 80));
+                }
+                let text = windows.iter()
+                    .map(|(start, end)| format!("{} - {}", sim_date(sim_time.0 + *start as f32).format("%d.%m.%Y"), sim_date(sim_time.0 + *end as f32).format("%d.%m.%Y")))
+                    .collect::<Vec<String>>()
+                    .join("\n");
+                response.on_hover_text(if text.is_empty() { "Not observable".to_string() } else { text });
+                ui.end_row();
+            }
+        });
+    });
+    if !open {
+        targets.show = false;
+    }
+}
+
+pub fn telescope_ui(
+    mut egui_context: ResMut<EguiContext>,
+    mut telescope: ResMut<Telescope>,
+    targets: Res<Targets>,
+    bodies: Query<&Name, (With<Selection>, Without<LagrangePoint>, Without<MinorBody>)>
+) {
+    if !telescope.enabled {
+        return;
+    }
+    let texture = egui_context.add_image(telescope.image.clone());
+    let mut open = true;
+    egui::Window::new("Telescope view")
+    .open(&mut open)
+    .resizable(false)
+    .show(egui_context.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("telescope_instrument")
+            .selected_text(format!("{:?}", telescope.instrument))
+            .show_ui(ui, |ui| {
+                for instrument in [Instrument::NIRCam, Instrument::MIRI] {
+                    ui.selectable_value(&mut telescope.instrument, instrument, format!("{:?}", instrument));
+                }
+            });
+            egui::ComboBox::from_id_source("telescope_pointing")
+            .selected_text(telescope.pointing.clone().unwrap_or_default())
+            .width(200.0)
+            .show_ui(ui, |ui| {
+                for name in targets.targets.iter().map(|target| target.name.as_str()).chain(bodies.iter().map(|name| name.as_str())) {
+                    if name == "JWST" {
+                        continue;
+                    }
+                    ui.selectable_value(&mut telescope.pointing, Some(name.to_string()), name);
+                }
+            });
+        });
+        let (width, height) = telescope.instrument.field_of_view();
+        ui.label(format!("Field of view: {:.2}' x {:.2}'", width, height));
+        ui.image(texture, egui::vec2(512.0, 512.0 * height / width));
+        if let Some(target) = targets.targets.iter().find(|target| Some(&target.name) == telescope.pointing.as_ref()) {
+            if !target.observable() {
+                ui.label(RichText::new(format!("Outside the field of regard ({:.1}° from the Sun)", target.elongation)).color(egui::Color32::LIGHT_RED));
+            }
+        }
+    });
+    if !open {
+        telescope.enabled = false;
+    }
+}
+
+/// Elevation, range and light time of JWST from each ground station and the passes over the next days
+pub fn ground_stations_ui(
+    mut egui_context: ResMut<EguiContext>,
+    mut ground_stations: ResMut<GroundStations>,
+    sim_time: Res<SimTime>
+) {
+    if !ground_stations.show {
+        return;
+    }
+    let mut open = true;
+    egui::Window::new("DSN ground stations")
+    .open(&mut open)
+    .default_width(420.0)
+    .show(egui_context.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Elevation mask");
+            ui.add(egui::DragValue::new(&mut ground_stations.elevation_mask).clamp_range(0.0..=45.0).speed(0.5).suffix("°"));
+        });
+        let samples = TIMELINE_HOURS * SAMPLES_PER_HOUR;
+        egui::Grid::new("ground_stations").striped(true).show(ui, |ui| {
+            ui.label("Station");
+            ui.label("Elevation");
+            ui.label("Range");
+            ui.label("Light time");
+            ui.label(format!("Next {} hours", TIMELINE_HOURS));
+            ui.end_row();
+            for station in ground_stations.stations.iter() {
+                let color = if station.elevation >= ground_stations.elevation_mask { egui::Color32::LIGHT_GREEN } else { egui::Color32::GRAY };
+                ui.label(RichText::new(&station.name).color(color));
+                ui.label(format!("{:.1}°", station.elevation));
+                ui.label(format!("{:.0} km", station.range));
+                ui.label(format!("{:.2} s", station.light_time()));
+                let (rect, response) = ui.allocate_exact_size(egui::vec2(samples as f32 * 0.4, 12.0), egui::Sense::hover());
+                let painter = ui.painter_at(rect);
+                painter.rect_filled(rect, 0.0, egui::Color32::from_gray(40));
+                let sample_width = rect.width() / samples as f32;
+                let passes = station.passes();
+                for (start, end) in passes.iter() {
+                    let pass = egui::Rect::from_min_max(
+                        egui::pos2(rect.left() + *start as f32 * sample_width, rect.top()),
+                        egui::pos2(rect.left() + (*end + 1) as f32 * sample_width, rect.bottom())
+                    );
+                    painter.rect_filled(pass, 0.0, egui::Color32::from_rgb(60, 120, 180));
+                }
+                let time = |sample: usize| sim_time.0 + sample as f32 / (24 * SAMPLES_PER_HOUR) as f32;
+                let text = passes.iter()
+                    .map(|(start, end)| format!("{} - {}", sim_date(time(*start)).format("%d.%m. %H:%M"), sim_date(time(*end + 1)).format("%d.%m. %H:%M")))
+                    .collect::<Vec<String>>()
+                    .join("\n");
+                response.on_hover_text(if text.is_empty() { "No pass".to_string() } else { text });
+                ui.end_row();
+            }
+        });
+    });
+    if !open {
+        ground_stations.show = false;
+    }
+}